@@ -0,0 +1,416 @@
+//! Opens prompt files in the user's editors.
+//!
+//! An editor is a command template. It is split into words the way a shell
+//! would (quotes group words, nothing else is interpreted), `{path}` is
+//! replaced by the prompt file, and a `$VISUAL` or `$EDITOR` word by the
+//! editor set in the environment. Nothing runs through a shell, so a file
+//! name can never turn into a command.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use crate::storage::{self, EditorCommand, StorageError};
+
+const PATH_TOKEN: &str = "{path}";
+
+/// Editors found by [`detect_installed`]: id, name, program, and whether it
+/// needs a terminal to run in.
+const KNOWN_EDITORS: &[(&str, &str, &str, bool)] = &[
+    ("cursor", "Cursor", "cursor", false),
+    ("vscode", "VS Code", "code", false),
+    ("vscodium", "VSCodium", "codium", false),
+    ("zed", "Zed", "zed", false),
+    ("sublime", "Sublime Text", "subl", false),
+    ("kate", "Kate", "kate", false),
+    (
+        "gnome-text-editor",
+        "Text Editor",
+        "gnome-text-editor",
+        false,
+    ),
+    ("gedit", "gedit", "gedit", false),
+    ("emacs", "Emacs", "emacs", false),
+    ("nvim", "Neovim", "nvim", true),
+    ("vim", "Vim", "vim", true),
+    ("helix", "Helix", "hx", true),
+    ("nano", "nano", "nano", true),
+];
+
+/// Terminals that terminal editors are started in, with the flag that comes
+/// before the command to run.
+const TERMINALS: &[(&str, &str)] = &[
+    ("x-terminal-emulator", "-e"),
+    ("gnome-terminal", "--"),
+    ("konsole", "-e"),
+    ("kitty", ""),
+    ("alacritty", "-e"),
+    ("wezterm", "start --"),
+    ("foot", ""),
+];
+
+/// Programs that hand the file to an app and exit right away. They are
+/// waited on, so an app that is not installed is reported instead of failing
+/// silently.
+const LAUNCHERS: &[&str] = &["open", "xdg-open"];
+
+fn editor(id: &str, name: &str, command: &str) -> EditorCommand {
+    EditorCommand {
+        id: id.to_string(),
+        name: name.to_string(),
+        command: command.to_string(),
+    }
+}
+
+/// Offered until the user saves their own list.
+pub fn defaults() -> Vec<EditorCommand> {
+    #[cfg(target_os = "macos")]
+    let mut editors = vec![
+        editor("cursor", "Cursor", "open -a Cursor {path}"),
+        editor("vscode", "VS Code", "open -a \"Visual Studio Code\" {path}"),
+        editor("zed", "Zed", "open -a Zed {path}"),
+    ];
+    #[cfg(not(target_os = "macos"))]
+    let mut editors = vec![
+        editor("cursor", "Cursor", "cursor {path}"),
+        editor("vscode", "VS Code", "code {path}"),
+        editor("zed", "Zed", "zed {path}"),
+    ];
+    let visual = env::var("VISUAL")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok());
+    if let Some(command) = visual.as_deref().and_then(visual_command) {
+        editors.push(editor("visual", "$VISUAL", &command));
+    }
+    editors
+}
+
+/// The command for the editor set in `$VISUAL`/`$EDITOR`. Started from the
+/// app there is no tty, so anything but a known GUI editor (usually vim or
+/// nano) runs in a terminal, and is not offered if there is none.
+fn visual_command(value: &str) -> Option<String> {
+    let program = split_words(value).ok()?.into_iter().next()?;
+    let name = Path::new(&program).file_name()?.to_str()?.to_string();
+    let is_gui = KNOWN_EDITORS
+        .iter()
+        .any(|&(_, _, known, needs_terminal)| known == name && !needs_terminal);
+    if is_gui {
+        Some(format!("$VISUAL {PATH_TOKEN}"))
+    } else {
+        in_terminal("$VISUAL")
+    }
+}
+
+/// `program {path}` run in the first terminal found on `PATH`.
+fn in_terminal(program: &str) -> Option<String> {
+    let (term, flag) = TERMINALS
+        .iter()
+        .find(|(term, _)| find_on_path(term).is_some())?;
+    Some(
+        [*term, flag, program, PATH_TOKEN]
+            .iter()
+            .filter(|word| !word.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// The user's editors, or the defaults if they never set any up.
+pub fn configured() -> Vec<EditorCommand> {
+    storage::saved_editors().unwrap_or_else(defaults)
+}
+
+/// Known editors whose program is on `PATH`, ready to be added to the list.
+pub fn detect_installed() -> Vec<EditorCommand> {
+    KNOWN_EDITORS
+        .iter()
+        .filter(|(_, _, program, _)| find_on_path(program).is_some())
+        .filter_map(|&(id, name, program, needs_terminal)| {
+            let command = if !needs_terminal {
+                format!("{program} {PATH_TOKEN}")
+            } else {
+                in_terminal(program)?
+            };
+            Some(editor(id, name, &command))
+        })
+        .collect()
+}
+
+/// Starts `editor` on `path` without waiting for it to close.
+pub fn open(editor: &EditorCommand, path: &Path) -> Result<(), StorageError> {
+    let failed = |message: String| StorageError::Editor { message };
+    let words = expand(&editor.command, path).map_err(failed)?;
+    match spawn(&words) {
+        Ok(()) => Ok(()),
+        // Not installed, or not on the PATH desktop launchers give us: let the
+        // desktop pick an app for the file instead.
+        #[cfg(target_os = "linux")]
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            spawn(&["xdg-open".to_string(), path.to_string_lossy().to_string()])
+                .map_err(|e| failed(format!("Failed to open {} or xdg-open: {}", editor.name, e)))
+        }
+        Err(e) => Err(failed(format!("Failed to open {}: {}", editor.name, e))),
+    }
+}
+
+fn spawn(words: &[String]) -> io::Result<()> {
+    let Some((program, args)) = words.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty command"));
+    };
+    let is_launcher = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| LAUNCHERS.contains(&name));
+    if is_launcher {
+        let output = Command::new(program).args(args).output()?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(match stderr.trim() {
+            "" => format!("{} exited with {}", program, output.status),
+            reason => reason.to_string(),
+        }));
+    }
+    let mut child = Command::new(program).args(args).spawn()?;
+    // Reap the process once the editor exits; terminal editors can stay open
+    // for hours.
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Turns a command template into the program and its arguments.
+fn expand(template: &str, path: &Path) -> Result<Vec<String>, String> {
+    let path = path.to_string_lossy();
+    let mut words = vec![];
+    let mut has_path = false;
+    for word in split_words(template)? {
+        match word.as_str() {
+            "$VISUAL" | "$EDITOR" => {
+                let value = (word == "$VISUAL")
+                    .then(|| env::var("VISUAL").ok())
+                    .flatten()
+                    .or_else(|| env::var("EDITOR").ok())
+                    .filter(|value| !value.trim().is_empty())
+                    .ok_or_else(|| format!("{} is not set.", word))?;
+                words.extend(split_words(&value)?);
+            }
+            _ => {
+                has_path |= word.contains(PATH_TOKEN);
+                words.push(word.replace(PATH_TOKEN, &path));
+            }
+        }
+    }
+    if words.is_empty() {
+        return Err("Editor command is empty.".to_string());
+    }
+    if !has_path {
+        words.push(path.to_string());
+    }
+    Ok(words)
+}
+
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for ch in command.chars() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Unclosed quote in editor command: {}", command));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    find_in(program, env::split_paths(&paths))
+}
+
+fn find_in(program: &str, dirs: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    let names: Vec<String> = if cfg!(windows) {
+        ["exe", "cmd", "bat"]
+            .iter()
+            .map(|ext| format!("{program}.{ext}"))
+            .collect()
+    } else {
+        vec![program.to_string()]
+    };
+    dirs.into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process;
+    use std::sync::{Mutex, OnceLock};
+
+    /// Held by tests that change `VISUAL` or `EDITOR`, which `expand` and
+    /// `defaults` read.
+    fn env_lock() -> std::sync::MutexGuard<'static, ()> {
+        static ENV: OnceLock<Mutex<()>> = OnceLock::new();
+        ENV.get_or_init(|| Mutex::new(()))
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[test]
+    fn split_words_honours_quotes() {
+        assert_eq!(
+            split_words(r#"open -a "Visual Studio Code" {path}"#).expect("split"),
+            vec!["open", "-a", "Visual Studio Code", "{path}"]
+        );
+        assert_eq!(
+            split_words("  code   -w '' ").expect("split"),
+            vec!["code", "-w", ""]
+        );
+        assert!(split_words("code \"unclosed").is_err());
+    }
+
+    #[test]
+    fn expand_fills_in_the_path_and_editor_variables() {
+        let path = Path::new("/lib/my prompt.md");
+        assert_eq!(
+            expand("code -g {path}", path).expect("expand"),
+            vec!["code", "-g", "/lib/my prompt.md"]
+        );
+        assert_eq!(
+            expand("zed", path).expect("expand"),
+            vec!["zed", "/lib/my prompt.md"]
+        );
+        assert_eq!(
+            expand("emacs --file={path}", path).expect("expand"),
+            vec!["emacs", "--file=/lib/my prompt.md"]
+        );
+
+        let _env = env_lock();
+        let previous = ["VISUAL", "EDITOR"].map(|name| (name, env::var_os(name)));
+        env::remove_var("VISUAL");
+        env::set_var("EDITOR", "nvim -p");
+        assert_eq!(
+            expand("kitty $VISUAL {path}", path).expect("expand"),
+            vec!["kitty", "nvim", "-p", "/lib/my prompt.md"]
+        );
+        env::set_var("VISUAL", "code -w");
+        assert_eq!(
+            expand("$VISUAL", path).expect("expand"),
+            vec!["code", "-w", "/lib/my prompt.md"]
+        );
+        env::remove_var("VISUAL");
+        env::remove_var("EDITOR");
+        assert!(expand("$EDITOR {path}", path).is_err());
+        for (name, value) in previous {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+
+    #[test]
+    fn visual_command_runs_terminal_editors_in_a_terminal() {
+        assert_eq!(
+            visual_command("/usr/bin/code -w").as_deref(),
+            Some("$VISUAL {path}")
+        );
+        for value in ["vim", "/usr/local/bin/nano", "my-editor --flag"] {
+            if let Some(command) = visual_command(value) {
+                assert!(!command.starts_with("$VISUAL"), "{command}");
+                assert!(command.ends_with("$VISUAL {path}"), "{command}");
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failing_launcher_is_reported() {
+        // Links, not scripts: a script written here could still be open in a
+        // process another test is forking, and fail to run.
+        let dir = env::temp_dir().join(format!("promptbook-launcher-tests-{}", process::id()));
+        fs::create_dir_all(dir.join("ok")).expect("create test dir");
+        let launcher = dir.join("open");
+        std::os::unix::fs::symlink("/bin/false", &launcher).expect("link launcher");
+        std::os::unix::fs::symlink("/bin/true", dir.join("ok").join("open"))
+            .expect("link launcher");
+        let cursor = |launcher: &Path| {
+            editor(
+                "cursor",
+                "Cursor",
+                &format!("{} -a Cursor {{path}}", launcher.display()),
+            )
+        };
+
+        let err = open(&cursor(&launcher), Path::new("/lib/a.md")).expect_err("launcher failed");
+        let StorageError::Editor { message } = err else {
+            panic!("expected an editor error, got {err:?}");
+        };
+        assert!(message.starts_with("Failed to open Cursor:"), "{message}");
+        assert!(open(
+            &cursor(&dir.join("ok").join("open")),
+            Path::new("/lib/a.md")
+        )
+        .is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_in_only_returns_executables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("promptbook-editors-tests-{}", process::id()));
+        fs::create_dir_all(&dir).expect("create test dir");
+        let script = dir.join("my-editor");
+        fs::write(&script, "#!/bin/sh\n").expect("write script");
+        fs::write(dir.join("not-executable"), "").expect("write file");
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+
+        assert_eq!(find_in("my-editor", [dir.clone()]), Some(script));
+        assert_eq!(find_in("not-executable", [dir.clone()]), None);
+        assert_eq!(find_in("missing", [dir.clone()]), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
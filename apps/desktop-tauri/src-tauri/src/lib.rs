@@ -1,15 +1,17 @@
-use tauri::menu::MenuBuilder;
+use serde::Serialize;
+use tauri::menu::{CheckMenuItemBuilder, Menu, MenuBuilder, SubmenuBuilder};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::Emitter;
 use tauri::{Manager, Position, Size, WindowEvent};
+mod editors;
 mod storage;
+mod watcher;
 #[cfg(target_os = "macos")]
 use objc2_app_kit::{NSApplication, NSImage};
 #[cfg(target_os = "macos")]
 use objc2_foundation::NSData;
-use std::fs;
-use std::process::Command;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
 #[cfg(target_os = "macos")]
 use tauri_nspanel::objc2::AnyThread;
 #[cfg(target_os = "macos")]
@@ -24,95 +26,589 @@ fn ping() -> &'static str {
 }
 
 #[tauri::command]
-fn load_prompts() -> Result<Vec<storage::PromptRecord>, String> {
-    storage::load_prompts()
+fn load_prompts(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<Vec<storage::PromptRecord>, storage::StorageError> {
+    library.current()?.load_prompts()
 }
 
 #[tauri::command]
 fn save_prompts(
+    library: tauri::State<'_, ActiveLibrary>,
     window: tauri::Window,
     app: tauri::AppHandle,
     prompts: Vec<storage::PromptRecord>,
-) -> Result<(), String> {
-    storage::save_prompts(prompts)?;
-    let payload = serde_json::json!({
-        "source": window.label()
-    });
+    force: Option<bool>,
+) -> Result<(), storage::StorageError> {
+    if force.unwrap_or(false) {
+        library.current()?.force_save_prompts(prompts)?;
+    } else {
+        library.current()?.save_prompts(prompts)?;
+    }
+    emit_prompts_updated(&app, &window, PromptsChange::default());
+    Ok(())
+}
+
+/// Describes what a mutation touched so other windows can patch their state.
+/// An empty change asks listeners to reload everything.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptsChange {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deleted: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order: Option<Vec<String>>,
+    /// Prompt files changed outside the app, relative to the library folder.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PromptsUpdatedPayload<'a> {
+    source: &'a str,
+    #[serde(flatten)]
+    change: PromptsChange,
+}
+
+fn emit_prompts_updated(app: &tauri::AppHandle, window: &tauri::Window, change: PromptsChange) {
+    let payload = PromptsUpdatedPayload {
+        source: window.label(),
+        change,
+    };
     // Global emit so all windows receive the event, including NSPanel windows
     // where targeted emit_to may not be delivered reliably.
     let _ = app.emit("prompts-updated", payload);
+}
+
+#[tauri::command]
+fn get_prompts(
+    library: tauri::State<'_, ActiveLibrary>,
+    ids: Vec<String>,
+) -> Result<Vec<storage::PromptRecord>, storage::StorageError> {
+    library.current()?.get_prompts(&ids)
+}
+
+#[tauri::command]
+fn create_prompt(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt: storage::PromptRecord,
+) -> Result<(), storage::StorageError> {
+    let id = prompt.id.clone();
+    library.current()?.create_prompt(prompt)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            changed: vec![id],
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn update_prompt(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt: storage::PromptRecord,
+) -> Result<(), storage::StorageError> {
+    let id = prompt.id.clone();
+    library.current()?.update_prompt(prompt)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            changed: vec![id],
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn get_prompt_conflict(
+    library: tauri::State<'_, ActiveLibrary>,
+    prompt_id: String,
+) -> Result<storage::PromptConflict, storage::StorageError> {
+    library.current()?.get_prompt_conflict(&prompt_id)
+}
+
+#[tauri::command]
+fn resolve_prompt_conflict(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt: storage::PromptRecord,
+) -> Result<(), storage::StorageError> {
+    let id = prompt.id.clone();
+    library.current()?.resolve_prompt_conflict(prompt)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            changed: vec![id],
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_prompt(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt_id: String,
+) -> Result<(), storage::StorageError> {
+    library.current()?.delete_prompt(&prompt_id)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            deleted: vec![prompt_id],
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+/// Deletes several prompts behind the same mass-deletion guard as `save_prompts`.
+#[tauri::command]
+fn delete_prompts(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt_ids: Vec<String>,
+    force: Option<bool>,
+) -> Result<(), storage::StorageError> {
+    library
+        .current()?
+        .delete_prompts(&prompt_ids, force.unwrap_or(false))?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            deleted: prompt_ids,
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn reorder_prompts(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+) -> Result<(), storage::StorageError> {
+    library.current()?.reorder_prompts(&ids)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            order: Some(ids),
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn rename_prompt_file(
+    library: tauri::State<'_, ActiveLibrary>,
+    prompt_id: String,
+) -> Result<String, storage::StorageError> {
+    library.current()?.rename_prompt_file(&prompt_id)
+}
+
+#[tauri::command]
+fn get_prompt_path(
+    library: tauri::State<'_, ActiveLibrary>,
+    prompt_id: String,
+    title: String,
+) -> Result<String, storage::StorageError> {
+    library.current()?.get_prompt_path(&prompt_id, &title)
+}
+
+#[tauri::command]
+fn get_storage_dir(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<String, storage::StorageError> {
+    Ok(library.current()?.root().to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn set_storage_dir(
+    app: tauri::AppHandle,
+    path: String,
+    move_files: Option<bool>,
+) -> Result<String, storage::StorageError> {
+    let library = storage::set_storage_dir(&path, move_files.unwrap_or(false))?;
+    let dir = library.root().to_string_lossy().to_string();
+    use_library(&app, library);
+    Ok(dir)
+}
+
+#[tauri::command]
+fn list_libraries() -> Result<Vec<storage::LibraryInfo>, storage::StorageError> {
+    storage::list_libraries()
+}
+
+#[tauri::command]
+fn register_library(
+    app: tauri::AppHandle,
+    name: String,
+    path: String,
+) -> Result<storage::LibraryInfo, storage::StorageError> {
+    let registered = storage::register_library(&name, &path)?;
+    refresh_tray_menu(&app);
+    Ok(registered)
+}
+
+#[tauri::command]
+fn switch_library(app: tauri::AppHandle, library_id: String) -> Result<(), storage::StorageError> {
+    use_library(&app, storage::switch_library(&library_id)?);
     Ok(())
 }
 
 #[tauri::command]
-fn get_prompt_path(prompt_id: String, title: String) -> Result<String, String> {
-    storage::get_prompt_path(&prompt_id, &title)
+fn remove_library(app: tauri::AppHandle, library_id: String) -> Result<(), storage::StorageError> {
+    use_library(&app, storage::remove_library(&library_id)?);
+    Ok(())
+}
+
+/// The library every command works on. Resolved on first use so the app
+/// still starts when no location can be worked out (e.g. `HOME` unset).
+#[derive(Default)]
+struct ActiveLibrary {
+    library: Mutex<Option<storage::Library>>,
+    /// Set once the windows listen, so recoveries found before that wait for
+    /// the first command instead of going unheard.
+    app: OnceLock<tauri::AppHandle>,
 }
 
-fn slugify_title(title: &str) -> String {
-    let mut out = String::new();
-    let mut prev_dash = false;
-    for ch in title.chars().flat_map(|c| c.to_lowercase()) {
-        if ch.is_ascii_alphanumeric() {
-            out.push(ch);
-            prev_dash = false;
-        } else if !prev_dash {
-            out.push('-');
-            prev_dash = true;
+impl ActiveLibrary {
+    fn current(&self) -> Result<CurrentLibrary, storage::StorageError> {
+        let mut slot = self.library.lock().map_err(|_| storage::StorageError::Io {
+            message: "library state is poisoned".to_string(),
+        })?;
+        let library = match slot.as_ref() {
+            Some(library) => library.clone(),
+            None => {
+                let library = storage::active_library()?;
+                *slot = Some(library.clone());
+                library
+            }
+        };
+        Ok(CurrentLibrary {
+            library,
+            app: self.app.get().cloned(),
+        })
+    }
+
+    fn set(&self, library: storage::Library) {
+        if let Ok(mut slot) = self.library.lock() {
+            *slot = Some(library);
         }
     }
-    let trimmed = out.trim_matches('-');
-    if trimmed.is_empty() {
-        "untitled".to_string()
-    } else {
-        trimmed.chars().take(72).collect()
+}
+
+/// The active library for the length of one command. Any command can be the
+/// one that finds a broken index, so each reports the recoveries once it is
+/// done with the library.
+struct CurrentLibrary {
+    library: storage::Library,
+    app: Option<tauri::AppHandle>,
+}
+
+impl std::ops::Deref for CurrentLibrary {
+    type Target = storage::Library;
+
+    fn deref(&self) -> &storage::Library {
+        &self.library
     }
 }
 
-#[tauri::command]
-fn open_prompt_in_editor(editor: String, title: String, content: String) -> Result<(), String> {
-    let home = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
-    let temp_dir = std::path::PathBuf::from(home)
-        .join(".config")
-        .join("promptbook")
-        .join(".tmp");
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("create temp dir: {}", e))?;
+impl Drop for CurrentLibrary {
+    fn drop(&mut self) {
+        if let Some(app) = &self.app {
+            report_index_recoveries(app, &self.library);
+        }
+    }
+}
 
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-    let file_name = format!("{}-{}.md", slugify_title(&title), ts);
-    let file_path = temp_dir.join(file_name);
-    fs::write(&file_path, content).map_err(|e| format!("write temp prompt: {}", e))?;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexRecoveredPayload<'a> {
+    library: String,
+    #[serde(flatten)]
+    recovery: &'a storage::IndexRecovery,
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        let app_name = match editor.as_str() {
-            "cursor" => "Cursor",
-            "vscode" => "Visual Studio Code",
-            "zed" => "Zed",
-            _ => return Err("Unsupported editor".to_string()),
-        };
+fn report_index_recoveries(app: &tauri::AppHandle, library: &storage::Library) {
+    for recovery in library.take_index_recoveries() {
+        let _ = app.emit(
+            "index-recovered",
+            IndexRecoveredPayload {
+                library: library.root().to_string_lossy().to_string(),
+                recovery: &recovery,
+            },
+        );
+    }
+}
 
-        let status = Command::new("open")
-            .arg("-a")
-            .arg(app_name)
-            .arg(&file_path)
-            .status()
-            .map_err(|e| format!("open editor: {}", e))?;
+/// Watches the active library so edits made in other programs reach every
+/// window.
+#[derive(Default)]
+struct ActiveWatcher(Mutex<Option<watcher::LibraryWatcher>>);
 
-        if status.success() {
-            return Ok(());
+/// Points the watcher at `library`, dropping the one on the previous library.
+fn watch_library(app: &tauri::AppHandle, library: &storage::Library) {
+    let handle = app.clone();
+    let watched = library.clone();
+    let started = watcher::watch(library.root(), move |files| {
+        // Our own saves show up too; only report what someone else changed.
+        let files = watched.changed_externally(files).unwrap_or_default();
+        report_index_recoveries(&handle, &watched);
+        if files.is_empty() {
+            return;
         }
-        Err(format!("Failed to open {}.", app_name))
+        let _ = handle.emit(
+            "prompts-updated",
+            PromptsUpdatedPayload {
+                source: "watcher",
+                change: PromptsChange {
+                    files,
+                    ..Default::default()
+                },
+            },
+        );
+    });
+    if let Ok(mut slot) = app.state::<ActiveWatcher>().0.lock() {
+        *slot = started.ok();
     }
+}
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        let _ = editor;
-        Err("Open in editor is only supported on macOS right now.".to_string())
+/// Makes `library` the active one and asks every window, including the one
+/// that asked for the switch, to reload.
+fn use_library(app: &tauri::AppHandle, library: storage::Library) {
+    report_index_recoveries(app, &library);
+    watch_library(app, &library);
+    app.state::<ActiveLibrary>().set(library);
+    refresh_tray_menu(app);
+    let _ = app.emit(
+        "prompts-updated",
+        PromptsUpdatedPayload {
+            source: "library",
+            change: PromptsChange::default(),
+        },
+    );
+}
+
+#[tauri::command]
+fn list_folders(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<Vec<String>, storage::StorageError> {
+    library.current()?.list_folders()
+}
+
+#[tauri::command]
+fn create_folder(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    folder: String,
+) -> Result<String, storage::StorageError> {
+    let created = library.current()?.create_folder(&folder)?;
+    emit_prompts_updated(&app, &window, PromptsChange::default());
+    Ok(created)
+}
+
+#[tauri::command]
+fn rename_folder(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    from: String,
+    to: String,
+) -> Result<String, storage::StorageError> {
+    let renamed = library.current()?.rename_folder(&from, &to)?;
+    emit_prompts_updated(&app, &window, PromptsChange::default());
+    Ok(renamed)
+}
+
+#[tauri::command]
+fn delete_folder(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    folder: String,
+) -> Result<(), storage::StorageError> {
+    library.current()?.delete_folder(&folder)?;
+    emit_prompts_updated(&app, &window, PromptsChange::default());
+    Ok(())
+}
+
+#[tauri::command]
+fn list_trash(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<Vec<storage::TrashEntry>, storage::StorageError> {
+    library.current()?.list_trash()
+}
+
+#[tauri::command]
+fn restore_from_trash(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    trash_id: String,
+) -> Result<storage::PromptRecord, storage::StorageError> {
+    let restored = library.current()?.restore_from_trash(&trash_id)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            changed: vec![restored.id.clone()],
+            ..Default::default()
+        },
+    );
+    Ok(restored)
+}
+
+#[tauri::command]
+fn empty_trash(library: tauri::State<'_, ActiveLibrary>) -> Result<usize, storage::StorageError> {
+    library.current()?.empty_trash()
+}
+
+#[tauri::command]
+fn list_prompt_revisions(
+    library: tauri::State<'_, ActiveLibrary>,
+    prompt_id: String,
+) -> Result<Vec<storage::RevisionInfo>, storage::StorageError> {
+    library.current()?.list_prompt_revisions(&prompt_id)
+}
+
+#[tauri::command]
+fn get_prompt_revision(
+    library: tauri::State<'_, ActiveLibrary>,
+    prompt_id: String,
+    revision_id: String,
+) -> Result<storage::PromptRevision, storage::StorageError> {
+    library
+        .current()?
+        .get_prompt_revision(&prompt_id, &revision_id)
+}
+
+#[tauri::command]
+fn restore_prompt_revision(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    prompt_id: String,
+    revision_id: String,
+) -> Result<storage::PromptRecord, storage::StorageError> {
+    let restored = library
+        .current()?
+        .restore_prompt_revision(&prompt_id, &revision_id)?;
+    emit_prompts_updated(
+        &app,
+        &window,
+        PromptsChange {
+            changed: vec![prompt_id],
+            ..Default::default()
+        },
+    );
+    Ok(restored)
+}
+
+#[tauri::command]
+fn get_library_settings(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<storage::LibrarySettings, storage::StorageError> {
+    library.current()?.get_library_settings()
+}
+
+#[tauri::command]
+fn update_library_settings(
+    library: tauri::State<'_, ActiveLibrary>,
+    settings: storage::LibrarySettings,
+) -> Result<(), storage::StorageError> {
+    library.current()?.update_library_settings(settings)
+}
+
+#[tauri::command]
+fn list_tags(
+    library: tauri::State<'_, ActiveLibrary>,
+) -> Result<Vec<storage::TagSummary>, storage::StorageError> {
+    library.current()?.list_tags()
+}
+
+#[tauri::command]
+fn prompts_with_tag(
+    library: tauri::State<'_, ActiveLibrary>,
+    tag: String,
+) -> Result<Vec<storage::PromptRecord>, storage::StorageError> {
+    library.current()?.prompts_with_tag(&tag)
+}
+
+#[tauri::command]
+fn rename_tag(
+    library: tauri::State<'_, ActiveLibrary>,
+    window: tauri::Window,
+    app: tauri::AppHandle,
+    from: String,
+    to: String,
+) -> Result<usize, storage::StorageError> {
+    let changed = library.current()?.rename_tag(&from, &to)?;
+    if changed > 0 {
+        emit_prompts_updated(&app, &window, PromptsChange::default());
     }
+    Ok(changed)
+}
+
+/// Opens the prompt's own file, so whatever is saved in the editor is the
+/// prompt. The storage watcher brings those edits back into every window.
+/// Callers save pending changes first so the file is current.
+#[tauri::command]
+fn open_prompt_in_editor(
+    library: tauri::State<'_, ActiveLibrary>,
+    editor: String,
+    prompt_id: String,
+    title: String,
+) -> Result<(), storage::StorageError> {
+    let file_path = library.current()?.get_prompt_path(&prompt_id, &title)?;
+    let Some(command) = editors::configured().into_iter().find(|e| e.id == editor) else {
+        return Err(storage::StorageError::NotFound {
+            message: format!("Unknown editor: {}", editor),
+        });
+    };
+    editors::open(&command, Path::new(&file_path))
+}
+
+#[tauri::command]
+fn list_editors() -> Vec<storage::EditorCommand> {
+    editors::configured()
+}
+
+#[tauri::command]
+fn set_editors(
+    app: tauri::AppHandle,
+    editors: Vec<storage::EditorCommand>,
+) -> Result<(), storage::StorageError> {
+    storage::set_editors(editors)?;
+    let _ = app.emit("editors-updated", ());
+    Ok(())
+}
+
+#[tauri::command]
+fn detect_editors() -> Vec<storage::EditorCommand> {
+    editors::detect_installed()
 }
 
 #[cfg(target_os = "macos")]
@@ -351,6 +847,45 @@ fn toggle_menubar_window(app: &tauri::AppHandle, rect: tauri::Rect) {
     }
 }
 
+const TRAY_ID: &str = "main";
+const LIBRARY_MENU_PREFIX: &str = "library:";
+
+fn build_tray_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let app_name = app
+        .config()
+        .product_name
+        .clone()
+        .unwrap_or_else(|| app.package_info().name.clone());
+
+    let mut libraries = SubmenuBuilder::new(app, "Library");
+    for library in storage::list_libraries().unwrap_or_default() {
+        let item = CheckMenuItemBuilder::with_id(
+            format!("{LIBRARY_MENU_PREFIX}{}", library.id),
+            &library.name,
+        )
+        .checked(library.active)
+        .build(app)?;
+        libraries = libraries.item(&item);
+    }
+
+    MenuBuilder::new(app)
+        .text("open", "Open Full Window")
+        .item(&libraries.build()?)
+        .separator()
+        .text("quit", format!("Quit {app_name}"))
+        .build()
+}
+
+/// Rebuilds the tray menu so the library list and check marks stay current.
+fn refresh_tray_menu(app: &tauri::AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Ok(menu) = build_tray_menu(app) {
+        let _ = tray.set_menu(Some(menu));
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -358,6 +893,8 @@ pub fn run() {
         .plugin(tauri_nspanel::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .manage(ActiveLibrary::default())
+        .manage(ActiveWatcher::default())
         .on_window_event(|window, event| match event {
             WindowEvent::CloseRequested { api, .. } if window.label() == "main" => {
                 api.prevent_close();
@@ -379,26 +916,29 @@ pub fn run() {
                 let _ = init_menubar_panel(app.app_handle());
             }
 
-            let app_name = app
-                .config()
-                .product_name
-                .clone()
-                .unwrap_or_else(|| app.package_info().name.clone());
+            if let Ok(library) = app.state::<ActiveLibrary>().current() {
+                let _ = library.purge_expired_trash();
+                let _ = library.sweep_temp_files();
+                watch_library(app.handle(), &library);
+            }
+            let _ = app.state::<ActiveLibrary>().app.set(app.handle().clone());
 
-            let tray_menu = MenuBuilder::new(app)
-                .text("open", "Open Full Window")
-                .separator()
-                .text("quit", format!("Quit {app_name}"))
-                .build()?;
+            let tray_menu = build_tray_menu(app.handle())?;
 
-            TrayIconBuilder::new()
+            TrayIconBuilder::with_id(TRAY_ID)
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&tray_menu)
                 .show_menu_on_left_click(false)
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "open" => show_main_window(app),
                     "quit" => app.exit(0),
-                    _ => {}
+                    id => {
+                        if let Some(library_id) = id.strip_prefix(LIBRARY_MENU_PREFIX) {
+                            if let Ok(library) = storage::switch_library(library_id) {
+                                use_library(app, library);
+                            }
+                        }
+                    }
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click {
@@ -422,8 +962,41 @@ pub fn run() {
             open_main_window_for_prompt,
             load_prompts,
             save_prompts,
+            get_prompts,
+            create_prompt,
+            update_prompt,
+            get_prompt_conflict,
+            resolve_prompt_conflict,
+            delete_prompt,
+            delete_prompts,
+            reorder_prompts,
+            rename_prompt_file,
             get_prompt_path,
-            open_prompt_in_editor
+            get_storage_dir,
+            set_storage_dir,
+            list_libraries,
+            register_library,
+            switch_library,
+            remove_library,
+            open_prompt_in_editor,
+            list_editors,
+            set_editors,
+            detect_editors,
+            list_folders,
+            create_folder,
+            rename_folder,
+            delete_folder,
+            list_tags,
+            prompts_with_tag,
+            rename_tag,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            list_prompt_revisions,
+            get_prompt_revision,
+            restore_prompt_revision,
+            get_library_settings,
+            update_library_settings
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
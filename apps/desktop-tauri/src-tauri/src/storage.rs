@@ -1,10 +1,37 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+mod conflicts;
+mod encoding;
+mod error;
+mod front_matter;
+mod history;
+mod ids;
+mod line_endings;
+mod location;
+mod lock;
+mod migrations;
+mod paths;
+mod recovery;
+mod settings;
+mod temp_files;
+mod trash;
+
+pub use conflicts::PromptConflict;
+use encoding::Encoding;
+pub use error::StorageError;
+use front_matter::FrontMatter;
+pub use history::RevisionInfo;
+use line_endings::LineEndings;
+use migrations::INDEX_VERSION;
+pub use recovery::IndexRecovery;
+pub use settings::LibrarySettings;
+pub use trash::TrashEntry;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PromptRecord {
@@ -13,23 +40,93 @@ pub struct PromptRecord {
     pub content: String,
     pub copied: u32,
     pub searched: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: String,
+    /// Set when the file could not be read as text. The prompt is read-only
+    /// until the file is fixed, and saves leave the file alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRevision {
+    pub id: String,
+    pub created_at: u64,
+    pub title: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSummary {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Handle to one prompt library: a folder of markdown prompts with its own
+/// index, settings, history and trash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    root: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryInfo {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub active: bool,
+}
+
+impl LibraryInfo {
+    fn new(entry: location::LibraryEntry, active_id: &str) -> Self {
+        Self {
+            active: entry.id == active_id,
+            id: entry.id,
+            name: entry.name,
+            path: entry.path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// An external editor prompts can be opened in. `command` is a template such
+/// as `code {path}`; the launcher fills in the prompt file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorCommand {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PromptIndexEntry {
     id: String,
     file: String,
+    #[serde(default)]
+    folder: String,
     title: String,
     copied: u32,
     searched: u32,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    created_at: u64,
     updated_at: u64,
+    /// Hash of the file as the app last read or wrote it.
+    #[serde(default)]
+    hash: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PromptIndex {
-    version: u8,
+    version: u32,
     prompts: Vec<PromptIndexEntry>,
 }
 
@@ -40,15 +137,137 @@ fn now_unix_secs() -> u64 {
         .unwrap_or(0)
 }
 
-fn storage_dir() -> Result<PathBuf, String> {
-    let home = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
-    Ok(PathBuf::from(home).join(".config").join("promptbook"))
+fn modified_unix_secs(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 fn index_path(base: &Path) -> PathBuf {
     base.join("index.json")
 }
 
+fn folder_of(file: &str) -> &str {
+    file.rsplit_once('/')
+        .map(|(folder, _)| folder)
+        .unwrap_or("")
+}
+
+fn normalize_folder(folder: &str) -> Result<String, StorageError> {
+    let mut parts = vec![];
+    for part in folder.split(['/', '\\']) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if part.starts_with('.') {
+            return Err(StorageError::invalid(format!(
+                "Invalid folder name: {}",
+                folder
+            )));
+        }
+        parts.push(part);
+    }
+    Ok(parts.join("/"))
+}
+
+fn join_folder(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{folder}/{name}")
+    }
+}
+
+fn walk_storage(
+    base: &Path,
+    folder: &str,
+    files: &mut Vec<String>,
+    folders: &mut Vec<String>,
+) -> Result<(), StorageError> {
+    let dir = base.join(folder);
+    for entry in
+        fs::read_dir(&dir).map_err(|e| StorageError::io(format!("read dir {:?}", dir), e))?
+    {
+        let Ok(entry) = entry else { continue };
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        // Dot-entries hold our own bookkeeping (.tmp and friends).
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let rel = join_folder(folder, &name);
+        if file_type.is_dir() {
+            folders.push(rel.clone());
+            walk_storage(base, &rel, files, folders)?;
+        } else if file_type.is_file() && name.ends_with(".md") {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+fn prompt_files(base: &Path) -> Result<Vec<String>, StorageError> {
+    let mut files = vec![];
+    walk_storage(base, "", &mut files, &mut vec![])?;
+    Ok(files)
+}
+
+fn file_stem(file: &str) -> &str {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+fn move_prompt_file(base: &Path, from: &str, to: &str) -> Result<(), StorageError> {
+    let target = base.join(to);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| StorageError::io("create folder", e))?;
+    }
+    fs::rename(base.join(from), &target)
+        .map_err(|e| StorageError::io(format!("rename {} -> {}", from, to), e))?;
+    remove_empty_folders(base, folder_of(from));
+    Ok(())
+}
+
+fn remove_empty_folders(base: &Path, folder: &str) {
+    let mut current = folder.to_string();
+    while !current.is_empty() {
+        let dir = base.join(&current);
+        let is_empty = fs::read_dir(&dir)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+        if !is_empty || fs::remove_dir(&dir).is_err() {
+            return;
+        }
+        current = folder_of(&current).to_string();
+    }
+}
+
+/// Removes `dir` bottom-up if it holds nothing but empty folders, and returns
+/// whether it is gone. Files, dot-entries and symlinks are never removed.
+fn remove_empty_tree(dir: &Path) -> io::Result<bool> {
+    let mut empty = true;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            empty &= remove_empty_tree(&entry.path())?;
+        } else {
+            empty = false;
+        }
+    }
+    if empty {
+        fs::remove_dir(dir)?;
+    }
+    Ok(empty)
+}
+
 fn prompt_file_for(base: &Path, prompt_id: &str, title: &str) -> Option<PathBuf> {
     let index = read_index(base);
     if let Some(entry) = index.prompts.into_iter().find(|p| p.id == prompt_id) {
@@ -58,35 +277,80 @@ fn prompt_file_for(base: &Path, prompt_id: &str, title: &str) -> Option<PathBuf>
         }
     }
 
-    let fallback = base.join(format!("{}.md", slugify(title)));
-    if fallback.exists() {
-        Some(fallback)
+    let fallback = format!("{}.md", slugify(title));
+    let path = base.join(&fallback);
+    if paths::is_prompt_file(base, &fallback) && path.exists() {
+        Some(path)
     } else {
         None
     }
 }
 
-fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), String> {
+fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), StorageError> {
     let tmp = path.with_extension(format!(
         "{}.tmp",
         path.extension().and_then(|e| e.to_str()).unwrap_or("file")
     ));
     {
-        let mut file = fs::File::create(&tmp).map_err(|e| format!("create {:?}: {}", tmp, e))?;
+        let mut file =
+            fs::File::create(&tmp).map_err(|e| StorageError::io(format!("create {:?}", tmp), e))?;
         file.write_all(bytes)
-            .map_err(|e| format!("write {:?}: {}", tmp, e))?;
+            .map_err(|e| StorageError::io(format!("write {:?}", tmp), e))?;
         file.sync_all()
-            .map_err(|e| format!("sync {:?}: {}", tmp, e))?;
+            .map_err(|e| StorageError::io(format!("sync {:?}", tmp), e))?;
     }
-    fs::rename(&tmp, path).map_err(|e| format!("rename {:?} -> {:?}: {}", tmp, path, e))?;
+    fs::rename(&tmp, path)
+        .map_err(|e| StorageError::io(format!("rename {:?} -> {:?}", tmp, path), e))?;
     Ok(())
 }
 
-fn slugify(title: &str) -> String {
+const SLUG_MAX_CHARS: usize = 80;
+/// Leaves room for a `-N` suffix and `.md` under the usual 255-byte limit
+/// on file names, even for scripts that take three or four bytes a char.
+const SLUG_MAX_BYTES: usize = 200;
+
+/// Names Windows refuses to create, whatever the extension.
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Letters and digits from any script, plus the combining marks and vowel
+/// signs that belong to them. Separators, punctuation, symbols and emoji
+/// are not kept.
+fn is_slug_char(ch: char) -> bool {
+    if ch.is_ascii() || ch.is_alphanumeric() {
+        return ch.is_alphanumeric();
+    }
+    !ch.is_whitespace()
+        && !ch.is_control()
+        && !matches!(
+            ch as u32,
+            0x00A0..=0x00BF
+                | 0x00D7
+                | 0x00F7
+                | 0x2000..=0x2BFF
+                | 0x3000..=0x303F
+                | 0xFE00..=0xFE6F
+                | 0xFEFF
+                | 0xFF00..=0xFF0F
+                | 0xFF1A..=0xFF20
+                | 0xFF3B..=0xFF40
+                | 0xFF5B..=0xFF65
+                | 0xFFF0..=0xFFFF
+                | 0x1F000..=0x1FAFF
+                | 0xE0000..=0xE007F
+        )
+}
+
+pub fn slugify(title: &str) -> String {
     let mut out = String::new();
-    let mut prev_dash = false;
+    let mut prev_dash = true;
     for ch in title.chars().flat_map(|c| c.to_lowercase()) {
-        if ch.is_ascii_alphanumeric() {
+        if is_slug_char(ch) {
+            if out.chars().count() >= SLUG_MAX_CHARS || out.len() + ch.len_utf8() > SLUG_MAX_BYTES {
+                break;
+            }
             out.push(ch);
             prev_dash = false;
         } else if !prev_dash {
@@ -94,370 +358,3261 @@ fn slugify(title: &str) -> String {
             prev_dash = true;
         }
     }
-    let trimmed = out.trim_matches('-').to_string();
+    let trimmed = out.trim_matches('-');
     if trimmed.is_empty() {
         "untitled".to_string()
+    } else if RESERVED_NAMES.contains(&trimmed) {
+        format!("{trimmed}-prompt")
     } else {
-        trimmed.chars().take(80).collect()
+        trimmed.to_string()
     }
 }
 
 fn unslug(stem: &str) -> String {
-    let mut s = stem.replace('-', " ");
-    if let Some(first) = s.get_mut(0..1) {
-        first.make_ascii_uppercase();
+    let spaced = stem.replace('-', " ");
+    let mut chars = spaced.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => spaced,
     }
-    s
 }
 
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .collect()
+}
+
+fn tag_matches(tag: &str, wanted: &str) -> bool {
+    tag.to_lowercase() == wanted.trim().to_lowercase()
+}
+
+fn file_matches_slug(file: &str, slug: &str) -> bool {
+    let Some(rest) = file
+        .strip_suffix(".md")
+        .and_then(|stem| stem.strip_prefix(slug))
+    else {
+        return false;
+    };
+    rest.is_empty()
+        || rest
+            .strip_prefix('-')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Case-insensitive filesystems (the macOS and Windows defaults) treat
+/// `Notes.md` and `notes.md` as one file, so names are compared lowercased.
 fn unique_filename(base_slug: &str, used: &mut HashSet<String>) -> String {
+    let taken: HashSet<String> = used.iter().map(|name| name.to_lowercase()).collect();
     let mut candidate = format!("{base_slug}.md");
-    if used.insert(candidate.clone()) {
-        return candidate;
-    }
     let mut i = 2;
-    loop {
+    while taken.contains(&candidate.to_lowercase()) {
         candidate = format!("{base_slug}-{i}.md");
-        if used.insert(candidate.clone()) {
-            return candidate;
-        }
         i += 1;
     }
+    used.insert(candidate.clone());
+    candidate
+}
+
+fn empty_index() -> PromptIndex {
+    PromptIndex {
+        version: INDEX_VERSION,
+        prompts: vec![],
+    }
 }
 
+/// Entries whose `file` would reach outside the library are dropped, so no
+/// caller ever joins one onto the root; the next index write forgets them.
 fn read_index(base: &Path) -> PromptIndex {
+    let mut index = parse_index(base);
+    index
+        .prompts
+        .retain(|entry| paths::is_prompt_file(base, &entry.file));
+    index
+}
+
+fn parse_index(base: &Path) -> PromptIndex {
+    let path = index_path(base);
+    let Ok(raw) = fs::read_to_string(&path) else {
+        return empty_index();
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&raw) else {
+        return recover_index(base, &raw);
+    };
+
+    let Ok(version) = migrations::supported_version(&value) else {
+        // Written by a newer release, or in a format no release wrote: use
+        // front matter only and leave the file alone (write_index refuses to
+        // replace it).
+        return empty_index();
+    };
+    if version == INDEX_VERSION {
+        return serde_json::from_value(value).unwrap_or_else(|_| recover_index(base, &raw));
+    }
+
+    let Ok(index) = migrations::migrate(value)
+        .and_then(|v| serde_json::from_value::<PromptIndex>(v).map_err(|e| e.to_string()))
+    else {
+        return recover_index(base, &raw);
+    };
+    let backup = base.join(format!("index.v{version}.json.bak"));
+    if fs::copy(&path, &backup).is_ok() {
+        let _ = write_index(base, index.prompts.clone());
+    }
+    index
+}
+
+/// Moves a broken index aside and rebuilds one from whatever survived, so
+/// the next save cannot overwrite ids, counters and ordering with nothing.
+fn recover_index(base: &Path, raw: &str) -> PromptIndex {
+    let prompts = recovery::recover(base, raw);
+    let _ = write_index(base, prompts.clone());
+    PromptIndex {
+        version: INDEX_VERSION,
+        prompts,
+    }
+}
+
+fn write_index(base: &Path, prompts: Vec<PromptIndexEntry>) -> Result<(), StorageError> {
+    let index = PromptIndex {
+        version: INDEX_VERSION,
+        prompts,
+    };
+    let json =
+        serde_json::to_vec_pretty(&index).map_err(|e| StorageError::json("serialize index", e))?;
     let path = index_path(base);
-    let raw = match fs::read_to_string(path) {
-        Ok(v) => v,
-        Err(_) => {
-            return PromptIndex {
-                version: 1,
-                prompts: vec![],
+    if let Ok(current) = fs::read(&path) {
+        if current == json {
+            return Ok(());
+        }
+        recovery::back_up(base, &current);
+        // An index that does not parse at all is recovered, not kept.
+        if let Ok(current) = serde_json::from_slice::<serde_json::Value>(&current) {
+            if let Err(message) = migrations::supported_version(&current) {
+                return Err(StorageError::CorruptIndex { message });
             }
         }
+    }
+    atomic_write(&path, &json)
+}
+
+fn index_entry_for(
+    path: &Path,
+    file: &str,
+    meta: FrontMatter,
+    cached: Option<&PromptIndexEntry>,
+    hash: String,
+) -> PromptIndexEntry {
+    let stem = file_stem(file);
+    let modified = || modified_unix_secs(path);
+    PromptIndexEntry {
+        id: meta
+            .id
+            .or_else(|| cached.map(|c| c.id.clone()))
+            .unwrap_or_else(|| stem.to_string()),
+        file: file.to_string(),
+        folder: folder_of(file).to_string(),
+        title: meta
+            .title
+            .or_else(|| cached.map(|c| c.title.clone()))
+            .unwrap_or_else(|| unslug(stem)),
+        copied: meta.copied.or(cached.map(|c| c.copied)).unwrap_or(0),
+        searched: meta.searched.or(cached.map(|c| c.searched)).unwrap_or(0),
+        tags: meta
+            .tags
+            .or_else(|| cached.map(|c| c.tags.clone()))
+            .unwrap_or_default(),
+        created_at: meta
+            .created
+            .or(cached.map(|c| c.created_at).filter(|t| *t > 0))
+            .unwrap_or_else(modified),
+        updated_at: meta
+            .updated
+            .or(cached.map(|c| c.updated_at).filter(|t| *t > 0))
+            .unwrap_or_else(modified),
+        hash,
+    }
+}
+
+#[derive(Default)]
+struct PromptText {
+    /// With `\n` line endings, whatever the file uses.
+    text: String,
+    encoding: Encoding,
+    line_endings: LineEndings,
+    /// Of the bytes on disk, which is what `changed_on_disk` compares.
+    hash: String,
+}
+
+/// A missing file reads as empty. One that exists but is not text is an
+/// error, so it can never be mistaken for an empty prompt and saved over.
+fn read_text(path: &Path) -> Result<PromptText, StorageError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(PromptText::default()),
+        Err(e) => return Err(StorageError::io(format!("read {:?}", path), e)),
     };
-    serde_json::from_str(&raw).unwrap_or(PromptIndex {
-        version: 1,
-        prompts: vec![],
+    let Some((text, encoding)) = encoding::decode(&bytes) else {
+        return Err(StorageError::Unreadable {
+            message: format!(
+                "{} is not a text file PromptBook can read, so it is left untouched.",
+                path.display()
+            ),
+        });
+    };
+    let line_endings = line_endings::detect(&text);
+    Ok(PromptText {
+        text: line_endings::to_lf(&text, line_endings),
+        encoding,
+        line_endings,
+        hash: conflicts::content_hash(&bytes),
     })
 }
 
-pub fn load_prompts() -> Result<Vec<PromptRecord>, String> {
-    let base = storage_dir()?;
-    fs::create_dir_all(&base).map_err(|e| format!("create storage dir: {}", e))?;
+/// Renders a prompt file with the encoding and line endings of `like`.
+fn render_like(meta: &FrontMatter, body: &str, like: &PromptText) -> Vec<u8> {
+    let mut rendered = front_matter::render(meta, body);
+    // The line ending after the closing `---` is the only one render adds.
+    if body.is_empty() && !like.line_endings.final_newline {
+        rendered.pop();
+    }
+    let rendered = line_endings::restore(&rendered, like.line_endings);
+    encoding::encode(&rendered, like.encoding)
+}
 
-    let index = read_index(&base);
-    let mut by_file: HashMap<String, PromptIndexEntry> = HashMap::new();
-    let mut order_by_file: HashMap<String, usize> = HashMap::new();
-    for (idx, p) in index.prompts.into_iter().enumerate() {
-        order_by_file.insert(p.file.clone(), idx);
-        by_file.insert(p.file.clone(), p);
+/// Gives a file that was dropped into the library an id of its own, kept in
+/// its front matter, so renaming it later keeps its counters and place. The
+/// file's dates are written too, since the write itself changes its mtime.
+/// A file whose id is in `taken`, a copy of another prompt, gets a new one.
+/// If the file cannot be written it keeps using its name as id.
+fn assign_id(base: &Path, file: &str, taken: &HashSet<String>) {
+    let path = base.join(file);
+    let Ok(text) = read_text(&path) else { return };
+    let (meta, body) = front_matter::split(&text.text);
+    let meta = meta.unwrap_or_default();
+    if meta.id.as_ref().is_some_and(|id| !taken.contains(id)) {
+        return;
     }
+    let modified = modified_unix_secs(&path);
+    let meta = FrontMatter {
+        id: Some(ids::ulid()),
+        created: meta.created.or(Some(modified)),
+        updated: meta.updated.or(Some(modified)),
+        ..meta
+    };
+    let _ = atomic_write(&path, &render_like(&meta, body, &text));
+}
 
-    let mut files: Vec<String> = fs::read_dir(&base)
-        .map_err(|e| format!("read dir: {}", e))?
-        .filter_map(|entry| entry.ok())
-        .filter_map(|entry| {
-            let p = entry.path();
-            if !p.is_file() {
-                return None;
-            }
-            let file = p.file_name()?.to_str()?.to_string();
-            if file == "index.json" || !file.ends_with(".md") {
-                return None;
-            }
-            Some(file)
-        })
+/// Files that appeared where an indexed file vanished with the same bytes,
+/// mapped to the file they replace: renamed or moved outside the app.
+fn find_renames(
+    base: &Path,
+    index: &[PromptIndexEntry],
+    files: &[String],
+) -> HashMap<String, String> {
+    let on_disk: HashSet<&str> = files.iter().map(String::as_str).collect();
+    let mut vanished: Vec<&PromptIndexEntry> = index
+        .iter()
+        .filter(|entry| !entry.hash.is_empty() && !on_disk.contains(entry.file.as_str()))
         .collect();
-
-    files.sort();
-    files.sort_by_key(|f| order_by_file.get(f).copied().unwrap_or(usize::MAX));
-
-    let mut out = vec![];
+    let mut renames = HashMap::new();
+    if vanished.is_empty() {
+        return renames;
+    }
     for file in files {
-        let path = base.join(&file);
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        let stem = file.trim_end_matches(".md");
-        if let Some(meta) = by_file.get(&file) {
-            out.push(PromptRecord {
-                id: meta.id.clone(),
-                title: meta.title.clone(),
-                content,
-                copied: meta.copied,
-                searched: meta.searched,
-            });
-        } else {
-            out.push(PromptRecord {
-                id: stem.to_string(),
-                title: unslug(stem),
-                content,
-                copied: 0,
-                searched: 0,
-            });
+        if index.iter().any(|entry| &entry.file == file) {
+            continue;
+        }
+        let Ok(bytes) = fs::read(base.join(file)) else {
+            continue;
+        };
+        let hash = conflicts::content_hash(&bytes);
+        if let Some(pos) = vanished.iter().position(|entry| entry.hash == hash) {
+            renames.insert(file.clone(), vanished.remove(pos).file.clone());
         }
     }
+    renames
+}
 
-    Ok(out)
+fn read_prompt(
+    base: &Path,
+    file: &str,
+    cached: Option<&PromptIndexEntry>,
+) -> (PromptRecord, PromptIndexEntry) {
+    let path = base.join(file);
+    let (text, load_error) = match read_text(&path) {
+        Ok(text) => (text, None),
+        Err(err) => (PromptText::default(), Some(err.to_string())),
+    };
+    let (meta, body) = front_matter::split(&text.text);
+    let entry = index_entry_for(&path, file, meta.unwrap_or_default(), cached, text.hash);
+    let record = PromptRecord {
+        id: entry.id.clone(),
+        title: entry.title.clone(),
+        content: body.to_string(),
+        copied: entry.copied,
+        searched: entry.searched,
+        tags: entry.tags.clone(),
+        folder: entry.folder.clone(),
+        load_error,
+    };
+    (record, entry)
 }
 
-pub fn save_prompts(prompts: Vec<PromptRecord>) -> Result<(), String> {
-    let base = storage_dir()?;
-    fs::create_dir_all(&base).map_err(|e| format!("create storage dir: {}", e))?;
+fn write_prompt(
+    base: &Path,
+    prompt: PromptRecord,
+    file_name: String,
+    prior: Option<&PromptIndexEntry>,
+    now: u64,
+) -> Result<PromptIndexEntry, StorageError> {
+    let folder = folder_of(&file_name).to_string();
+    if !folder.is_empty() {
+        fs::create_dir_all(base.join(&folder))
+            .map_err(|e| StorageError::io(format!("create folder {}", folder), e))?;
+    }
 
-    let existing_files: HashSet<String> = fs::read_dir(&base)
-        .map_err(|e| format!("read dir: {}", e))?
-        .filter_map(|entry| entry.ok())
-        .filter_map(|entry| entry.file_name().into_string().ok())
-        .filter(|name| name.ends_with(".md"))
-        .collect();
+    let prior_text = match prior {
+        Some(entry) => read_text(&base.join(&entry.file))?,
+        None => PromptText::default(),
+    };
+    let prior_raw = prior_text.text.as_str();
+    let (prior_meta, prior_body) = match front_matter::split(prior_raw) {
+        (Some(meta), body) => (meta, Some(body)),
+        (None, _) => (FrontMatter::default(), None),
+    };
+    // Files written before front matter was added are all body. A missing
+    // file has no hash and nothing to keep.
+    let prior_content = prior_body.or_else(|| (!prior_text.hash.is_empty()).then_some(prior_raw));
+    let created_at = prior
+        .map(|entry| entry.created_at)
+        .filter(|t| *t > 0)
+        .or(prior_meta.created)
+        .unwrap_or(now);
+
+    // Counters change on every copy; only edits the user made count as an update.
+    let edited = prior_body != Some(prompt.content.as_str())
+        || prior_meta.title.as_deref() != Some(prompt.title.as_str())
+        || prior_meta.tags.as_ref() != Some(&prompt.tags)
+        || prior.map(|entry| entry.folder.as_str()) != Some(folder.as_str());
+    let updated_at = if edited {
+        now
+    } else {
+        prior_meta
+            .updated
+            .or(prior.map(|entry| entry.updated_at).filter(|t| *t > 0))
+            .unwrap_or(now)
+    };
+
+    let meta = FrontMatter {
+        id: Some(prompt.id.clone()),
+        title: Some(prompt.title.clone()),
+        tags: Some(prompt.tags.clone()),
+        created: Some(created_at),
+        updated: Some(updated_at),
+        copied: Some(prompt.copied),
+        searched: Some(prompt.searched),
+        extra: prior_meta.extra,
+    };
+    let bytes = render_like(&meta, &prompt.content, &prior_text);
+    let hash = conflicts::content_hash(&bytes);
+    let unchanged = prior.is_some_and(|entry| entry.file == file_name) && hash == prior_text.hash;
+    if !unchanged {
+        if prior_content.is_some_and(|body| body != prompt.content) {
+            history::record(base, &prompt.id, prior_raw, &settings::read(base).history)?;
+        }
+        atomic_write(&base.join(&file_name), &bytes)?;
+    }
+    Ok(PromptIndexEntry {
+        id: prompt.id,
+        file: file_name,
+        folder,
+        title: prompt.title,
+        copied: prompt.copied,
+        searched: prompt.searched,
+        tags: prompt.tags,
+        created_at,
+        updated_at,
+        hash,
+    })
+}
 
-    let mut used_names = HashSet::new();
-    let mut index_entries = vec![];
-    let mut kept_files = HashSet::new();
+/// Whether someone else changed the file since the app last read or wrote it.
+/// Entries from before hashes were recorded are trusted.
+fn changed_on_disk(base: &Path, entry: &PromptIndexEntry) -> bool {
+    !entry.hash.is_empty()
+        && fs::read(base.join(&entry.file))
+            .is_ok_and(|raw| conflicts::content_hash(&raw) != entry.hash)
+}
 
-    for prompt in prompts {
-        let slug = slugify(&prompt.title);
-        let file_name = unique_filename(&slug, &mut used_names);
-        let file_path = base.join(&file_name);
-        atomic_write(&file_path, prompt.content.as_bytes())?;
-        kept_files.insert(file_name.clone());
-        index_entries.push(PromptIndexEntry {
-            id: prompt.id,
-            file: file_name,
-            title: prompt.title,
-            copied: prompt.copied,
-            searched: prompt.searched,
-            updated_at: now_unix_secs(),
-        });
+impl Library {
+    pub fn open(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
     }
 
-    for file in existing_files.difference(&kept_files) {
-        let path = base.join(file);
-        let _ = fs::remove_file(path);
+    pub fn root(&self) -> &Path {
+        &self.root
     }
 
-    let index = PromptIndex {
-        version: 1,
-        prompts: index_entries,
-    };
-    let json = serde_json::to_vec_pretty(&index).map_err(|e| format!("serialize index: {}", e))?;
-    atomic_write(&index_path(&base), &json)?;
-    Ok(())
-}
+    /// Held by every call that writes to the library, including loads, which
+    /// may refresh or repair the index.
+    fn lock(&self) -> Result<lock::LockGuard, StorageError> {
+        lock::acquire(&self.root, lock::TIMEOUT)
+    }
 
-pub fn get_prompt_path(prompt_id: &str, title: &str) -> Result<String, String> {
-    let base = storage_dir()?;
-    fs::create_dir_all(&base).map_err(|e| format!("create storage dir: {}", e))?;
-    let Some(path) = prompt_file_for(&base, prompt_id, title) else {
-        return Err("Prompt file not found yet. Try again after autosave.".to_string());
-    };
-    Ok(path.to_string_lossy().to_string())
-}
+    pub fn load_prompts(&self) -> Result<Vec<PromptRecord>, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+
+        let index = read_index(base);
+        let mut by_file: HashMap<String, PromptIndexEntry> = HashMap::new();
+        let mut order_by_file: HashMap<String, usize> = HashMap::new();
+        for (idx, p) in index.prompts.iter().enumerate() {
+            order_by_file.insert(p.file.clone(), idx);
+            by_file.insert(p.file.clone(), p.clone());
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::env;
-    use std::process;
-    use std::sync::{Mutex, MutexGuard, OnceLock};
+        let mut files = prompt_files(base)?;
+        for (file, old) in find_renames(base, &index.prompts, &files) {
+            if let Some(mut entry) = by_file.get(&old).cloned() {
+                // Without a title in its front matter, a file is named after itself.
+                entry.title = unslug(file_stem(&file));
+                order_by_file.insert(file.clone(), order_by_file[&old]);
+                by_file.insert(file, entry);
+            }
+        }
+        files.sort();
+        files.sort_by_key(|f| order_by_file.get(f).copied().unwrap_or(usize::MAX));
+
+        let mut out = vec![];
+        let mut entries = vec![];
+        let mut ids = HashSet::new();
+        for file in files {
+            let cached = by_file.get(&file);
+            if cached.is_none() {
+                assign_id(base, &file, &ids);
+            }
+            let (mut record, mut entry) = read_prompt(base, &file, cached);
+            // A copy made in a file manager or by git carries its original's id;
+            // the file listed first in the index keeps it.
+            if ids.contains(&entry.id) {
+                assign_id(base, &file, &ids);
+                (record, entry) = read_prompt(base, &file, None);
+                if ids.contains(&entry.id) {
+                    entry.id = ids::ulid();
+                    record.id = entry.id.clone();
+                }
+            }
+            ids.insert(entry.id.clone());
+            out.push(record);
+            entries.push(entry);
+        }
 
-    static HOME_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
+        // The index is only a cache of the front matter; refresh it whenever the
+        // files on disk tell a different story.
+        if entries != index.prompts {
+            let _ = write_index(base, entries);
+        }
 
-    fn home_mutex() -> &'static Mutex<()> {
-        HOME_MUTEX.get_or_init(|| Mutex::new(()))
+        Ok(out)
     }
 
-    struct TestHome {
-        _lock: MutexGuard<'static, ()>,
-        previous_home: Option<String>,
-        root: PathBuf,
+    pub fn save_prompts(&self, prompts: Vec<PromptRecord>) -> Result<(), StorageError> {
+        self.write_prompts(prompts, false)
     }
 
-    impl TestHome {
-        fn new() -> Self {
-            let lock = home_mutex().lock().expect("home lock poisoned");
-            let previous_home = env::var("HOME").ok();
+    /// Like [`save_prompts`] but skips the mass-deletion guard, for bulk deletes
+    /// the user has confirmed.
+    pub fn force_save_prompts(&self, prompts: Vec<PromptRecord>) -> Result<(), StorageError> {
+        self.write_prompts(prompts, true)
+    }
 
-            let unique = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("system time before epoch")
-                .as_nanos();
-            let root = env::temp_dir().join(format!(
-                "promptbook-storage-tests-{}-{}",
-                process::id(),
-                unique
-            ));
-            fs::create_dir_all(&root).expect("create test root");
+    fn write_prompts(&self, prompts: Vec<PromptRecord>, force: bool) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+
+        let existing_files: HashSet<String> = prompt_files(base)?.into_iter().collect();
+
+        let previous: HashMap<String, PromptIndexEntry> = read_index(base)
+            .prompts
+            .into_iter()
+            .map(|entry| (entry.id.clone(), entry))
+            .collect();
+
+        let now = now_unix_secs();
+        let mut claimed = HashSet::new();
+        let mut index_entries = vec![];
+        let mut kept_files = HashSet::new();
+
+        // Existing prompts keep the file they own.
+        let mut planned = vec![];
+        let mut conflicted = vec![];
+        for mut prompt in prompts {
+            prompt.tags = normalize_tags(prompt.tags);
+            let folder = normalize_folder(&prompt.folder)?;
+            let prior = previous.get(&prompt.id);
+            let owned = prior
+                .map(|entry| entry.file.clone())
+                .filter(|file| existing_files.contains(file) && claimed.insert(file.clone()));
+            if owned.is_some() && prior.is_some_and(|entry| changed_on_disk(base, entry)) {
+                conflicts::stash(base, &prompt)?;
+                conflicted.push(prompt.id.clone());
+            }
+            planned.push((prompt, folder, owned));
+        }
+        if !conflicted.is_empty() {
+            return Err(StorageError::ChangedOnDisk {
+                prompt_ids: conflicted,
+            });
+        }
 
-            env::set_var("HOME", &root);
+        // An empty or truncated list usually means the caller failed to load, not
+        // that the user wants the library gone.
+        let removing = existing_files.len().saturating_sub(claimed.len());
+        if !force
+            && settings::read(base)
+                .mass_delete
+                .blocks(existing_files.len(), removing)
+        {
+            return Err(StorageError::MassDeletion {
+                existing: existing_files.len(),
+                removing,
+            });
+        }
 
-            Self {
-                _lock: lock,
-                previous_home,
-                root,
+        // New and moved prompts never take the name of a file already on disk,
+        // even one about to be removed: that file goes to the trash, not under
+        // someone else's content.
+        let mut used_names = existing_files.clone();
+        for (prompt, folder, owned) in planned {
+            let prior = previous.get(&prompt.id);
+            // Whatever the caller sent for a file that could not be read was
+            // never its real content; keep the file as it is.
+            if let (Some(file), Some(entry)) = (&owned, prior) {
+                if read_text(&base.join(file)).is_err() {
+                    kept_files.insert(file.clone());
+                    index_entries.push(entry.clone());
+                    continue;
+                }
             }
+            let (file_name, prior) = match owned {
+                Some(file) if folder_of(&file) == folder => (file, prior.cloned()),
+                Some(file) => {
+                    let target =
+                        unique_filename(&join_folder(&folder, file_stem(&file)), &mut used_names);
+                    move_prompt_file(base, &file, &target)?;
+                    let moved = prior.map(|entry| PromptIndexEntry {
+                        file: target.clone(),
+                        ..entry.clone()
+                    });
+                    (target, moved)
+                }
+                None => {
+                    let slug = join_folder(&folder, &slugify(&prompt.title));
+                    (unique_filename(&slug, &mut used_names), None)
+                }
+            };
+            let entry = write_prompt(base, prompt, file_name, prior.as_ref(), now)?;
+            kept_files.insert(entry.file.clone());
+            index_entries.push(entry);
         }
 
-        fn storage_base(&self) -> PathBuf {
-            self.root.join(".config").join("promptbook")
+        let by_file: HashMap<&str, &PromptIndexEntry> = previous
+            .values()
+            .map(|entry| (entry.file.as_str(), entry))
+            .collect();
+        for file in existing_files.difference(&kept_files) {
+            // Files moved to a new folder above are already gone.
+            if base.join(file).is_file() {
+                let (record, _) = read_prompt(base, file, by_file.get(file.as_str()).copied());
+                trash::move_to_trash(base, file, &record.id, &record.title)?;
+            }
+            remove_empty_folders(base, folder_of(file));
         }
+
+        write_index(base, index_entries)
     }
 
-    impl Drop for TestHome {
-        fn drop(&mut self) {
-            if let Some(ref prev) = self.previous_home {
-                env::set_var("HOME", prev);
-            } else {
-                env::remove_var("HOME");
-            }
-            let _ = fs::remove_dir_all(&self.root);
+    pub fn get_prompts(&self, ids: &[String]) -> Result<Vec<PromptRecord>, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let mut out = vec![];
+        let mut refreshed = false;
+        for entry in index
+            .prompts
+            .iter_mut()
+            .filter(|entry| ids.contains(&entry.id) && base.join(&entry.file).is_file())
+        {
+            let (record, fresh) = read_prompt(base, &entry.file, Some(entry));
+            refreshed |= *entry != fresh;
+            *entry = fresh;
+            out.push(record);
         }
+        // Later saves are checked against the versions handed out here.
+        if refreshed {
+            let _ = write_index(base, index.prompts);
+        }
+        Ok(out)
     }
 
-    fn sample_prompt(id: &str, title: &str, content: &str) -> PromptRecord {
-        PromptRecord {
-            id: id.to_string(),
-            title: title.to_string(),
-            content: content.to_string(),
-            copied: 0,
-            searched: 0,
+    pub fn create_prompt(&self, mut prompt: PromptRecord) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+        let mut index = read_index(base);
+        if index.prompts.iter().any(|entry| entry.id == prompt.id) {
+            return Err(StorageError::conflict(format!(
+                "Prompt already exists: {}",
+                prompt.id
+            )));
         }
+
+        prompt.tags = normalize_tags(prompt.tags);
+        let folder = normalize_folder(&prompt.folder)?;
+        let mut used_names: HashSet<String> = prompt_files(base)?.into_iter().collect();
+        used_names.extend(index.prompts.iter().map(|entry| entry.file.clone()));
+        let slug = join_folder(&folder, &slugify(&prompt.title));
+        let file_name = unique_filename(&slug, &mut used_names);
+
+        let entry = write_prompt(base, prompt, file_name, None, now_unix_secs())?;
+        index.prompts.push(entry);
+        write_index(base, index.prompts)
     }
 
-    #[test]
-    fn slugify_handles_basic_and_empty_titles() {
-        assert_eq!(slugify("Bug Triage"), "bug-triage");
-        assert_eq!(slugify("  !!!  "), "untitled");
-        assert_eq!(slugify("Title___With---Noise"), "title-with-noise");
+    pub fn update_prompt(&self, mut prompt: PromptRecord) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let Some(pos) = index.prompts.iter().position(|entry| entry.id == prompt.id) else {
+            return Err(StorageError::not_found(format!(
+                "Prompt not found: {}",
+                prompt.id
+            )));
+        };
+
+        prompt.tags = normalize_tags(prompt.tags);
+        let folder = normalize_folder(&prompt.folder)?;
+        let mut prior = index.prompts[pos].clone();
+        read_text(&base.join(&prior.file))?;
+        if changed_on_disk(base, &prior) {
+            conflicts::stash(base, &prompt)?;
+            return Err(StorageError::ChangedOnDisk {
+                prompt_ids: vec![prompt.id],
+            });
+        }
+        if folder_of(&prior.file) != folder {
+            let mut used_names: HashSet<String> = prompt_files(base)?.into_iter().collect();
+            used_names.extend(index.prompts.iter().map(|entry| entry.file.clone()));
+            let target = unique_filename(
+                &join_folder(&folder, file_stem(&prior.file)),
+                &mut used_names,
+            );
+            if base.join(&prior.file).is_file() {
+                move_prompt_file(base, &prior.file, &target)?;
+            }
+            prior.file = target;
+        }
+
+        let file_name = prior.file.clone();
+        index.prompts[pos] = write_prompt(base, prompt, file_name, Some(&prior), now_unix_secs())?;
+        write_index(base, index.prompts)
     }
 
-    #[test]
-    fn slugify_truncates_to_eighty_chars() {
-        let long_title = "a".repeat(120);
-        let slug = slugify(&long_title);
-        assert_eq!(slug.len(), 80);
-        assert!(slug.chars().all(|c| c == 'a'));
+    /// Renames a prompt's file to match its current title. Title edits never do
+    /// this on their own so paths handed to editors and git stay valid.
+    pub fn rename_prompt_file(&self, prompt_id: &str) -> Result<String, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let Some(pos) = index.prompts.iter().position(|entry| entry.id == prompt_id) else {
+            return Err(StorageError::not_found(format!(
+                "Prompt not found: {}",
+                prompt_id
+            )));
+        };
+
+        let current = index.prompts[pos].file.clone();
+        let slug = join_folder(folder_of(&current), &slugify(&index.prompts[pos].title));
+        if file_matches_slug(&current, &slug) {
+            return Ok(current);
+        }
+
+        let mut used_names: HashSet<String> = prompt_files(base)?.into_iter().collect();
+        used_names.extend(index.prompts.iter().map(|entry| entry.file.clone()));
+        let target = unique_filename(&slug, &mut used_names);
+        move_prompt_file(base, &current, &target)?;
+        index.prompts[pos].file = target.clone();
+        write_index(base, index.prompts)?;
+        Ok(target)
     }
 
-    #[test]
-    fn unslug_restores_readable_title() {
-        assert_eq!(unslug("release-notes"), "Release notes");
+    pub fn delete_prompt(&self, prompt_id: &str) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let Some(pos) = index.prompts.iter().position(|entry| entry.id == prompt_id) else {
+            return Err(StorageError::not_found(format!(
+                "Prompt not found: {}",
+                prompt_id
+            )));
+        };
+        let entry = index.prompts.remove(pos);
+        if base.join(&entry.file).is_file() {
+            trash::move_to_trash(base, &entry.file, &entry.id, &entry.title)?;
+        }
+        remove_empty_folders(base, folder_of(&entry.file));
+        write_index(base, index.prompts)
     }
 
-    #[test]
-    fn unique_filename_adds_numeric_suffixes() {
-        let mut used = HashSet::new();
-        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage.md");
-        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage-2.md");
-        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage-3.md");
+    /// Deletes several prompts at once. Refuses as many deletions as
+    /// [`save_prompts`] would unless `force` is set; ids that are already gone
+    /// are skipped.
+    pub fn delete_prompts(&self, prompt_ids: &[String], force: bool) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let existing = prompt_files(base)?.len();
+        let index = read_index(base);
+        let removing = index
+            .prompts
+            .iter()
+            .filter(|entry| prompt_ids.contains(&entry.id) && base.join(&entry.file).is_file())
+            .count();
+        if !force && settings::read(base).mass_delete.blocks(existing, removing) {
+            return Err(StorageError::MassDeletion { existing, removing });
+        }
+        let indexed: HashSet<&str> = index
+            .prompts
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect();
+        for prompt_id in prompt_ids {
+            if indexed.contains(prompt_id.as_str()) {
+                self.delete_prompt(prompt_id)?;
+            }
+        }
+        Ok(())
     }
 
-    #[test]
-    fn read_index_defaults_when_missing_or_invalid() {
-        let test_home = TestHome::new();
-        let base = test_home.storage_base();
-        fs::create_dir_all(&base).expect("create storage base");
+    pub fn reorder_prompts(&self, ids: &[String]) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let order: HashMap<&str, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(pos, id)| (id.as_str(), pos))
+            .collect();
+        index
+            .prompts
+            .sort_by_key(|entry| order.get(entry.id.as_str()).copied().unwrap_or(usize::MAX));
+        write_index(base, index.prompts)
+    }
 
-        let missing = read_index(&base);
-        assert_eq!(missing.version, 1);
-        assert!(missing.prompts.is_empty());
+    /// The version a refused save tried to write, next to the one on disk.
+    pub fn get_prompt_conflict(&self, prompt_id: &str) -> Result<PromptConflict, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let entry = read_index(base)
+            .prompts
+            .into_iter()
+            .find(|entry| entry.id == prompt_id);
+        let (Some(local), Some(entry)) = (conflicts::stashed(base, prompt_id), entry) else {
+            return Err(StorageError::not_found(format!(
+                "No conflict recorded for prompt: {}",
+                prompt_id
+            )));
+        };
+        let (disk, _) = read_prompt(base, &entry.file, Some(&entry));
+        Ok(PromptConflict {
+            prompt_id: prompt_id.to_string(),
+            file: entry.file,
+            local,
+            disk,
+        })
+    }
 
-        fs::write(index_path(&base), "{not-json").expect("write invalid json");
-        let invalid = read_index(&base);
-        assert_eq!(invalid.version, 1);
-        assert!(invalid.prompts.is_empty());
+    /// Saves the version the user settled on (their own, the one on disk or a
+    /// merge) over whatever the file holds now.
+    pub fn resolve_prompt_conflict(&self, prompt: PromptRecord) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let mut index = read_index(base);
+        let Some(entry) = index.prompts.iter_mut().find(|entry| entry.id == prompt.id) else {
+            return Err(StorageError::not_found(format!(
+                "Prompt not found: {}",
+                prompt.id
+            )));
+        };
+        entry.hash = fs::read(base.join(&entry.file))
+            .map(|raw| conflicts::content_hash(&raw))
+            .unwrap_or_default();
+        write_index(base, index.prompts)?;
+        conflicts::clear(base, &prompt.id);
+        self.update_prompt(prompt)
     }
 
-    #[test]
-    fn save_and_load_roundtrip_preserves_order_and_content() {
-        let test_home = TestHome::new();
+    /// Of the given library-relative prompt files and folders, those that
+    /// differ from what the app last read or wrote: edited, new or gone. Lets a
+    /// file watcher tell the app's own writes apart from everyone else's.
+    pub fn changed_externally(&self, paths: Vec<String>) -> Result<Vec<String>, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let index = read_index(base);
+        let file_changed = |file: &str| {
+            let known = index.prompts.iter().find(|entry| entry.file == file);
+            match (known, fs::read(base.join(file))) {
+                (Some(entry), Ok(raw)) => conflicts::content_hash(&raw) != entry.hash,
+                (None, Ok(_)) | (Some(_), Err(_)) => true,
+                (None, Err(_)) => false,
+            }
+        };
+        // A folder renamed or removed outside the app only reports itself, not
+        // the prompts inside it.
+        let folder_changed = |folder: &str| {
+            let prefix = format!("{folder}/");
+            let known = index
+                .prompts
+                .iter()
+                .filter(|entry| entry.file.starts_with(&prefix))
+                .count();
+            if !base.join(folder).is_dir() {
+                return known > 0;
+            }
+            let mut files = vec![];
+            if walk_storage(base, folder, &mut files, &mut vec![]).is_err() {
+                return true;
+            }
+            // A new empty folder only shows up in the folder list.
+            files.is_empty() && known == 0
+                || files.len() != known
+                || files.iter().any(|file| file_changed(file))
+        };
+        Ok(paths
+            .into_iter()
+            .filter(|path| {
+                if paths::is_prompt_file(base, path) {
+                    file_changed(path)
+                } else {
+                    paths::is_folder(base, path) && folder_changed(path)
+                }
+            })
+            .collect())
+    }
+
+    pub fn list_trash(&self) -> Result<Vec<TrashEntry>, StorageError> {
+        Ok(trash::list(&self.root))
+    }
+
+    pub fn restore_from_trash(&self, trash_id: &str) -> Result<PromptRecord, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let (source, item) = trash::take(base, trash_id)?;
+        let mut index = read_index(base);
+        if index.prompts.iter().any(|entry| entry.id == item.prompt_id) {
+            return Err(StorageError::conflict(format!(
+                "A prompt with id {} already exists.",
+                item.prompt_id
+            )));
+        }
+
+        let folder = normalize_folder(folder_of(&item.file))?;
+        let mut used_names: HashSet<String> = prompt_files(base)?.into_iter().collect();
+        used_names.extend(index.prompts.iter().map(|entry| entry.file.clone()));
+        let target = unique_filename(
+            &join_folder(&folder, file_stem(&item.file)),
+            &mut used_names,
+        );
+        if !folder.is_empty() {
+            fs::create_dir_all(base.join(&folder))
+                .map_err(|e| StorageError::io("create folder", e))?;
+        }
+        fs::rename(&source, base.join(&target))
+            .map_err(|e| StorageError::io("restore prompt", e))?;
+        trash::forget(base, trash_id)?;
+
+        let fallback = PromptIndexEntry {
+            id: item.prompt_id,
+            file: target.clone(),
+            folder,
+            title: item.title,
+            copied: 0,
+            searched: 0,
+            tags: vec![],
+            created_at: 0,
+            updated_at: 0,
+            hash: String::new(),
+        };
+        let (record, entry) = read_prompt(base, &target, Some(&fallback));
+        index.prompts.push(entry);
+        write_index(base, index.prompts)?;
+        Ok(record)
+    }
+
+    pub fn empty_trash(&self) -> Result<usize, StorageError> {
+        let _lock = self.lock()?;
+        trash::empty(&self.root)
+    }
+
+    pub fn purge_expired_trash(&self) -> Result<usize, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        Ok(trash::purge(
+            base,
+            settings::read(base).trash.retention_days,
+        ))
+    }
+
+    /// Index recoveries in this library since the last call, for the UI to
+    /// report.
+    pub fn take_index_recoveries(&self) -> Vec<IndexRecovery> {
+        recovery::take_pending(&self.root)
+    }
+
+    /// Removes leftover temp files older than the library's configured age.
+    pub fn sweep_temp_files(&self) -> Result<usize, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let max_age_hours = settings::read(base).temp_files.max_age_hours;
+        if max_age_hours == 0 {
+            return Ok(0);
+        }
+        Ok(temp_files::sweep(
+            base,
+            std::time::Duration::from_secs(max_age_hours * 60 * 60),
+        ))
+    }
+
+    pub fn list_prompt_revisions(
+        &self,
+        prompt_id: &str,
+    ) -> Result<Vec<RevisionInfo>, StorageError> {
+        let base = &self.root;
+        Ok(history::list(base, prompt_id))
+    }
+
+    pub fn get_prompt_revision(
+        &self,
+        prompt_id: &str,
+        revision_id: &str,
+    ) -> Result<PromptRevision, StorageError> {
+        let base = &self.root;
+        let raw = history::read(base, prompt_id, revision_id)?;
+        let (meta, body) = front_matter::split(&raw);
+        Ok(PromptRevision {
+            id: revision_id.to_string(),
+            created_at: revision_id.parse::<u64>().unwrap_or(0) / 1000,
+            title: meta.and_then(|m| m.title),
+            content: body.to_string(),
+        })
+    }
+
+    /// Restores a revision through the regular update path, so the content being
+    /// replaced becomes a revision itself and the restore can be undone.
+    pub fn restore_prompt_revision(
+        &self,
+        prompt_id: &str,
+        revision_id: &str,
+    ) -> Result<PromptRecord, StorageError> {
+        let _lock = self.lock()?;
+        let revision = self.get_prompt_revision(prompt_id, revision_id)?;
+        let Some(mut prompt) = self
+            .get_prompts(&[prompt_id.to_string()])?
+            .into_iter()
+            .next()
+        else {
+            return Err(StorageError::not_found(format!(
+                "Prompt not found: {}",
+                prompt_id
+            )));
+        };
+        prompt.content = revision.content;
+        if let Some(title) = revision.title {
+            prompt.title = title;
+        }
+        self.update_prompt(prompt.clone())?;
+        Ok(prompt)
+    }
+
+    pub fn get_library_settings(&self) -> Result<LibrarySettings, StorageError> {
+        Ok(settings::read(&self.root))
+    }
+
+    pub fn update_library_settings(&self, settings: LibrarySettings) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+        settings::write(base, &settings)
+    }
+
+    pub fn list_tags(&self) -> Result<Vec<TagSummary>, StorageError> {
+        let mut counts: HashMap<String, TagSummary> = HashMap::new();
+        for tag in self.load_prompts()?.into_iter().flat_map(|p| p.tags) {
+            counts
+                .entry(tag.to_lowercase())
+                .or_insert_with(|| TagSummary {
+                    name: tag.clone(),
+                    count: 0,
+                })
+                .count += 1;
+        }
+        let mut tags: Vec<TagSummary> = counts.into_values().collect();
+        tags.sort_by_key(|t| t.name.to_lowercase());
+        Ok(tags)
+    }
+
+    pub fn prompts_with_tag(&self, tag: &str) -> Result<Vec<PromptRecord>, StorageError> {
+        Ok(self
+            .load_prompts()?
+            .into_iter()
+            .filter(|p| p.tags.iter().any(|t| tag_matches(t, tag)))
+            .collect())
+    }
+
+    pub fn rename_tag(&self, from: &str, to: &str) -> Result<usize, StorageError> {
+        let _lock = self.lock()?;
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(StorageError::invalid("Tag name cannot be empty."));
+        }
+        let mut prompts = self.load_prompts()?;
+        let mut changed = 0;
+        for prompt in &mut prompts {
+            if !prompt.tags.iter().any(|t| tag_matches(t, from)) {
+                continue;
+            }
+            let tags = std::mem::take(&mut prompt.tags)
+                .into_iter()
+                .map(|t| {
+                    if tag_matches(&t, from) {
+                        to.to_string()
+                    } else {
+                        t
+                    }
+                })
+                .collect();
+            prompt.tags = normalize_tags(tags);
+            changed += 1;
+        }
+        if changed > 0 {
+            self.save_prompts(prompts)?;
+        }
+        Ok(changed)
+    }
+
+    pub fn get_prompt_path(&self, prompt_id: &str, title: &str) -> Result<String, StorageError> {
+        // Reading the index may migrate or repair it.
+        let _lock = self.lock()?;
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+        let Some(path) = prompt_file_for(base, prompt_id, title) else {
+            return Err(StorageError::not_found(
+                "Prompt file not found yet. Try again after autosave.",
+            ));
+        };
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    pub fn list_folders(&self) -> Result<Vec<String>, StorageError> {
+        let base = &self.root;
+        fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+        let mut folders = vec![];
+        walk_storage(base, "", &mut vec![], &mut folders)?;
+        folders.sort();
+        Ok(folders)
+    }
+
+    pub fn create_folder(&self, folder: &str) -> Result<String, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let folder = normalize_folder(folder)?;
+        if folder.is_empty() {
+            return Err(StorageError::invalid("Folder name cannot be empty."));
+        }
+        fs::create_dir_all(base.join(&folder)).map_err(|e| StorageError::io("create folder", e))?;
+        Ok(folder)
+    }
+
+    pub fn rename_folder(&self, from: &str, to: &str) -> Result<String, StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let from = normalize_folder(from)?;
+        let to = normalize_folder(to)?;
+        if from.is_empty() || to.is_empty() {
+            return Err(StorageError::invalid("Folder name cannot be empty."));
+        }
+        if to == from || to.starts_with(&format!("{from}/")) {
+            return Err(StorageError::invalid("Cannot move a folder into itself."));
+        }
+        let source = base.join(&from);
+        let target = base.join(&to);
+        if !source.is_dir() {
+            return Err(StorageError::not_found(format!(
+                "Folder not found: {}",
+                from
+            )));
+        }
+        if target.exists() {
+            return Err(StorageError::conflict(format!(
+                "Folder already exists: {}",
+                to
+            )));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| StorageError::io("create folder", e))?;
+        }
+        fs::rename(&source, &target).map_err(|e| StorageError::io("rename folder", e))?;
+        remove_empty_folders(base, folder_of(&from));
+
+        let mut index = read_index(base);
+        for entry in &mut index.prompts {
+            let Some(rest) = entry.file.strip_prefix(&format!("{from}/")) else {
+                continue;
+            };
+            entry.file = format!("{to}/{rest}");
+            entry.folder = folder_of(&entry.file).to_string();
+        }
+        write_index(base, index.prompts)?;
+        Ok(to)
+    }
+
+    pub fn delete_folder(&self, folder: &str) -> Result<(), StorageError> {
+        let _lock = self.lock()?;
+        let base = &self.root;
+        let folder = normalize_folder(folder)?;
+        if folder.is_empty() {
+            return Err(StorageError::invalid("Folder name cannot be empty."));
+        }
+        let dir = base.join(&folder);
+        if !dir.is_dir() {
+            return Err(StorageError::not_found(format!(
+                "Folder not found: {}",
+                folder
+            )));
+        }
+        let mut files = vec![];
+        walk_storage(base, &folder, &mut files, &mut vec![])?;
+        if !files.is_empty() {
+            return Err(StorageError::conflict(
+                "Folder still contains prompts. Move or delete them first.",
+            ));
+        }
+        match remove_empty_tree(&dir) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(StorageError::conflict(
+                "Folder still contains other files. Move or delete them first.",
+            )),
+            Err(e) => Err(StorageError::io("delete folder", e)),
+        }
+    }
+}
+
+/// The library the app last switched to, or the default one.
+pub fn active_library() -> Result<Library, StorageError> {
+    Ok(Library::open(location::active_library()?.path))
+}
+
+pub fn list_libraries() -> Result<Vec<LibraryInfo>, StorageError> {
+    let active = location::active_library()?.id;
+    Ok(location::libraries()?
+        .into_iter()
+        .map(|entry| LibraryInfo::new(entry, &active))
+        .collect())
+}
+
+pub fn register_library(name: &str, path: &str) -> Result<LibraryInfo, StorageError> {
+    let entry = location::register_library(name, Path::new(path))?;
+    Ok(LibraryInfo::new(entry, ""))
+}
+
+pub fn switch_library(library_id: &str) -> Result<Library, StorageError> {
+    Ok(Library::open(location::switch_library(library_id)?.path))
+}
+
+/// Unregisters a library without touching its files. Returns the library
+/// that is active afterwards.
+pub fn remove_library(library_id: &str) -> Result<Library, StorageError> {
+    location::remove_library(library_id)?;
+    active_library()
+}
+
+/// The editors saved with [`set_editors`], or `None` while the user has not
+/// changed the built-in list.
+pub fn saved_editors() -> Option<Vec<EditorCommand>> {
+    location::editors()
+}
+
+pub fn set_editors(editors: Vec<EditorCommand>) -> Result<(), StorageError> {
+    location::set_editors(editors)
+}
+
+/// Points the active library at another folder, optionally moving its files
+/// (prompts, index, history and trash) there first.
+pub fn set_storage_dir(path: &str, move_files: bool) -> Result<Library, StorageError> {
+    Ok(Library::open(
+        location::set_storage_dir(Path::new(path), move_files)?.path,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use std::sync::{Mutex, MutexGuard, OnceLock};
+
+    static HOME_MUTEX: OnceLock<Mutex<()>> = OnceLock::new();
+
+    fn home_mutex() -> &'static Mutex<()> {
+        HOME_MUTEX.get_or_init(|| Mutex::new(()))
+    }
+
+    /// Variables that steer `storage_dir`; cleared for every test so the
+    /// developer's own environment never leaks in.
+    const LOCATION_VARS: [&str; 4] = [
+        "HOME",
+        location::HOME_OVERRIDE,
+        "XDG_CONFIG_HOME",
+        "XDG_DATA_HOME",
+    ];
+
+    struct TestHome {
+        _lock: MutexGuard<'static, ()>,
+        previous: Vec<(&'static str, Option<String>)>,
+        root: PathBuf,
+    }
+
+    impl TestHome {
+        fn new() -> Self {
+            let lock = home_mutex().lock().expect("home lock poisoned");
+            let previous = LOCATION_VARS
+                .iter()
+                .map(|name| (*name, env::var(name).ok()))
+                .collect();
+
+            let unique = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time before epoch")
+                .as_nanos();
+            let root = env::temp_dir().join(format!(
+                "promptbook-storage-tests-{}-{}",
+                process::id(),
+                unique
+            ));
+            fs::create_dir_all(&root).expect("create test root");
+
+            for name in LOCATION_VARS {
+                env::remove_var(name);
+            }
+            env::set_var("HOME", &root);
+
+            Self {
+                _lock: lock,
+                previous,
+                root,
+            }
+        }
+
+        fn storage_base(&self) -> PathBuf {
+            location::storage_dir().expect("resolve storage dir")
+        }
+    }
+
+    impl Drop for TestHome {
+        fn drop(&mut self) {
+            for (name, value) in &self.previous {
+                match value {
+                    Some(value) => env::set_var(name, value),
+                    None => env::remove_var(name),
+                }
+            }
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn library() -> Library {
+        active_library().expect("open library")
+    }
+
+    fn sample_prompt(id: &str, title: &str, content: &str) -> PromptRecord {
+        PromptRecord {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            copied: 0,
+            searched: 0,
+            tags: vec![],
+            folder: String::new(),
+            load_error: None,
+        }
+    }
+
+    fn sample_prompt_in(folder: &str, id: &str, title: &str, content: &str) -> PromptRecord {
+        PromptRecord {
+            folder: folder.to_string(),
+            ..sample_prompt(id, title, content)
+        }
+    }
+
+    #[test]
+    fn slugify_handles_basic_and_empty_titles() {
+        assert_eq!(slugify("Bug Triage"), "bug-triage");
+        assert_eq!(slugify("  !!!  "), "untitled");
+        assert_eq!(slugify("Title___With---Noise"), "title-with-noise");
+    }
+
+    #[test]
+    fn slugify_truncates_to_eighty_chars() {
+        let long_title = "a".repeat(120);
+        let slug = slugify(&long_title);
+        assert_eq!(slug.len(), 80);
+        assert!(slug.chars().all(|c| c == 'a'));
+    }
+
+    #[test]
+    fn slugify_keeps_letters_from_any_script() {
+        assert_eq!(slugify("コードレビュー"), "コードレビュー");
+        assert_eq!(slugify("Ελέγχος κώδικα"), "ελέγχος-κώδικα");
+        assert_eq!(slugify("Café ☕ notes"), "café-notes");
+        assert_eq!(slugify("हिन्दी सारांश"), "हिन्दी-सारांश");
+        assert_eq!(slugify("“Quoted” — draft…"), "quoted-draft");
+    }
+
+    #[test]
+    fn slugify_strips_separators_and_avoids_reserved_names() {
+        assert_eq!(slugify("../etc/passwd"), "etc-passwd");
+        assert_eq!(slugify("a\\b:c*d?"), "a-b-c-d");
+        assert_eq!(slugify("CON"), "con-prompt");
+        assert_eq!(slugify("lpt1"), "lpt1-prompt");
+        assert_eq!(slugify("console"), "console");
+    }
+
+    #[test]
+    fn slugify_limits_bytes_for_wide_scripts() {
+        let slug = slugify(&"漢".repeat(120));
+        assert_eq!(slug.len(), 198);
+        assert_eq!(slug.chars().count(), 66);
+        assert!(!slugify(&format!("{} b", "a".repeat(79))).ends_with('-'));
+    }
+
+    #[test]
+    fn unslug_restores_readable_title() {
+        assert_eq!(unslug("release-notes"), "Release notes");
+        assert_eq!(unslug("élan-vital"), "Élan vital");
+    }
+
+    #[test]
+    fn unique_filename_adds_numeric_suffixes() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage.md");
+        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage-2.md");
+        assert_eq!(unique_filename("bug-triage", &mut used), "bug-triage-3.md");
+    }
+
+    #[test]
+    fn unique_filename_ignores_case_when_checking_collisions() {
+        let mut used = HashSet::from(["Notes.md".to_string(), "work/Ideas.md".to_string()]);
+        assert_eq!(unique_filename("notes", &mut used), "notes-2.md");
+        assert_eq!(unique_filename("Work/ideas", &mut used), "Work/ideas-2.md");
+        assert_eq!(unique_filename("NOTES", &mut used), "NOTES-3.md");
+    }
+
+    #[test]
+    fn read_index_defaults_when_missing_or_invalid() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+
+        let missing = read_index(&base);
+        assert_eq!(missing.version, INDEX_VERSION);
+        assert!(missing.prompts.is_empty());
+
+        fs::write(index_path(&base), "{not-json").expect("write invalid json");
+        let invalid = read_index(&base);
+        assert_eq!(invalid.version, INDEX_VERSION);
+        assert!(invalid.prompts.is_empty());
+    }
+
+    #[test]
+    fn corrupt_index_is_quarantined_and_rebuilt_from_salvage_and_backup() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        let _ = library().take_index_recoveries();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Alpha", "First"),
+                sample_prompt("b", "Beta", "Second"),
+            ])
+            .expect("save prompts");
+        let mut prompts = library().load_prompts().expect("load prompts");
+        prompts[0].copied = 7;
+        prompts[1].copied = 9;
+        library().save_prompts(prompts).expect("save counters");
+
+        // Truncate mid-way through the second entry, and strip front matter so
+        // only the index remembers the counters.
+        let good = fs::read_to_string(index_path(&base)).expect("read index");
+        let cut = good.find("\"id\": \"b\"").expect("find entry b");
+        fs::write(index_path(&base), &good[..cut]).expect("corrupt index");
+        fs::write(base.join("alpha.md"), "First").expect("strip front matter");
+        fs::write(base.join("beta.md"), "Second").expect("strip front matter");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded.len(), 2);
+        assert_eq!((loaded[0].id.as_str(), loaded[0].copied), ("a", 7));
+        assert_eq!(loaded[1].id, "b");
+        assert_eq!(loaded[1].title, "Beta");
+
+        let other = Library::open(test_home.root.join("Other"));
+        other.load_prompts().expect("load other library");
+        assert!(other.take_index_recoveries().is_empty());
+        let recoveries = library().take_index_recoveries();
+        assert_eq!(recoveries.len(), 1);
+        assert_eq!(recoveries[0].salvaged, 1);
+        assert_eq!(recoveries[0].from_backup, 1);
+        let quarantined = recoveries[0].quarantined.clone().expect("quarantined");
+        assert!(quarantined.starts_with("index.json.corrupt-"));
+        assert_eq!(
+            fs::read_to_string(base.join(quarantined)).expect("read quarantine"),
+            good[..cut]
+        );
+        assert_eq!(read_index(&base).prompts.len(), 2);
+        assert!(library().take_index_recoveries().is_empty());
+    }
+
+    #[test]
+    fn read_index_migrates_v1_and_keeps_a_backup() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        let v1 = r#"{"version":1,"prompts":[{"id":"a","file":"bug-triage.md","title":"Bug Triage","copied":3,"searched":1,"updatedAt":1700000000}]}"#;
+        fs::write(index_path(&base), v1).expect("write v1 index");
+        fs::write(base.join("bug-triage.md"), "First").expect("write prompt");
+
+        let index = read_index(&base);
+        assert_eq!(index.version, INDEX_VERSION);
+        assert_eq!(index.prompts[0].copied, 3);
+        assert_eq!(index.prompts[0].created_at, 1700000000);
+
+        let backup = fs::read_to_string(base.join("index.v1.json.bak")).expect("read backup");
+        assert_eq!(backup, v1);
+        let upgraded: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(index_path(&base)).expect("read index"))
+                .expect("parse index");
+        assert_eq!(upgraded["version"], serde_json::json!(INDEX_VERSION));
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].id, "a");
+        assert_eq!(loaded[0].title, "Bug Triage");
+        assert_eq!(loaded[0].copied, 3);
+    }
+
+    #[test]
+    fn invalid_index_versions_are_left_alone() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        for raw in [
+            r#"{"version":0,"prompts":[{"id":"a","file":"a.md","title":"A"}]}"#,
+            r#"{"version":4294967298,"prompts":[]}"#,
+        ] {
+            fs::write(index_path(&base), raw).expect("write index");
+            assert!(read_index(&base).prompts.is_empty());
+            assert!(matches!(
+                library().save_prompts(vec![sample_prompt("a", "Bug Triage", "First")]),
+                Err(StorageError::CorruptIndex { .. })
+            ));
+            assert_eq!(
+                fs::read_to_string(index_path(&base)).expect("read index"),
+                raw
+            );
+            assert!(library().take_index_recoveries().is_empty());
+        }
+    }
+
+    #[test]
+    fn newer_index_versions_are_never_overwritten() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        let future = r#"{"version":99,"prompts":[],"shiny":true}"#;
+        fs::write(index_path(&base), future).expect("write future index");
+
+        assert!(read_index(&base).prompts.is_empty());
+        let err = library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect_err("expected refusal");
+        assert!(matches!(err, StorageError::CorruptIndex { .. }));
+        assert!(err.to_string().contains("format version 99"));
+        assert_eq!(
+            fs::read_to_string(index_path(&base)).expect("read index"),
+            future
+        );
+    }
+
+    #[test]
+    fn storage_dir_prefers_promptbook_home_then_saved_location() {
+        let test_home = TestHome::new();
+        let custom = test_home.root.join("Synced").join("prompts");
+        env::set_var(location::HOME_OVERRIDE, &custom);
+        assert_eq!(location::storage_dir().expect("storage dir"), custom);
+        assert!(matches!(
+            set_storage_dir("/elsewhere", false),
+            Err(StorageError::Conflict { .. })
+        ));
+
+        env::remove_var(location::HOME_OVERRIDE);
+        let saved = test_home.root.join("Chosen");
+        set_storage_dir(&saved.to_string_lossy(), false).expect("set storage dir");
+        assert_eq!(location::storage_dir().expect("storage dir"), saved);
+        assert!(saved.is_dir());
+        assert!(matches!(
+            set_storage_dir("relative/path", false),
+            Err(StorageError::Invalid { .. })
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn storage_dir_follows_xdg_on_linux_but_keeps_existing_libraries() {
+        let test_home = TestHome::new();
+        let data = test_home.root.join("xdg-data");
+        env::set_var("XDG_DATA_HOME", &data);
+        assert_eq!(
+            location::storage_dir().expect("storage dir"),
+            data.join("promptbook")
+        );
+
+        let legacy = test_home.root.join(".config").join("promptbook");
+        fs::create_dir_all(&legacy).expect("create legacy dir");
+        fs::write(legacy.join("config.json"), "{}").expect("write legacy config");
+        assert_eq!(
+            location::storage_dir().expect("storage dir"),
+            data.join("promptbook")
+        );
+        fs::write(legacy.join("old-prompt.md"), "Hello").expect("write legacy prompt");
+        assert_eq!(location::storage_dir().expect("storage dir"), legacy);
+
+        // The old library stays where it was even with the config moved.
+        let config = test_home.root.join("xdg-config");
+        env::set_var("XDG_CONFIG_HOME", &config);
+        assert_eq!(location::storage_dir().expect("storage dir"), legacy);
+        fs::remove_file(legacy.join("old-prompt.md")).expect("remove legacy prompt");
+        fs::write(index_path(&legacy), "{}").expect("write legacy index");
+        assert_eq!(location::storage_dir().expect("storage dir"), legacy);
+
+        let target = test_home.root.join("Moved");
+        set_storage_dir(&target.to_string_lossy(), false).expect("set storage dir");
+        assert!(config.join("promptbook").join("config.json").is_file());
+    }
+
+    #[test]
+    fn storage_dir_works_without_home_when_overridden() {
+        let test_home = TestHome::new();
+        env::remove_var("HOME");
+        assert_eq!(location::storage_dir(), Err(StorageError::HomeMissing));
+
+        env::set_var(location::HOME_OVERRIDE, test_home.root.join("lib"));
+        library()
+            .save_prompts(vec![sample_prompt("a", "Alpha", "First")])
+            .expect("save prompts");
+        assert_eq!(library().load_prompts().expect("load prompts").len(), 1);
+    }
+
+    #[test]
+    fn set_storage_dir_moves_the_library_when_asked() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Alpha", "First"),
+                sample_prompt_in("work", "b", "Beta", "Second"),
+            ])
+            .expect("save prompts");
+        let old_base = test_home.storage_base();
+
+        let target = test_home.root.join("Synced");
+        set_storage_dir(&target.to_string_lossy(), true).expect("move library");
+        assert_eq!(location::storage_dir().expect("storage dir"), target);
+        assert!(!old_base.join("alpha.md").exists());
+        assert!(target.join("work").join("beta.md").is_file());
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].folder, "work");
+
+        let occupied = test_home.root.join("Occupied");
+        fs::create_dir_all(&occupied).expect("create occupied dir");
+        fs::write(occupied.join("existing.md"), "Mine").expect("write existing prompt");
+        assert!(matches!(
+            set_storage_dir(&occupied.to_string_lossy(), true),
+            Err(StorageError::Conflict { .. })
+        ));
+        assert_eq!(location::storage_dir().expect("storage dir"), target);
+
+        // A clash with any one entry stops the move before anything goes.
+        let clashing = test_home.root.join("Clashing");
+        fs::create_dir_all(clashing.join("work")).expect("create clashing dir");
+        assert!(matches!(
+            set_storage_dir(&clashing.to_string_lossy(), true),
+            Err(StorageError::Conflict { .. })
+        ));
+        assert!(target.join("alpha.md").is_file());
+        assert!(!clashing.join("alpha.md").exists());
+        assert!(!clashing.join("index.json").exists());
+        assert_eq!(location::storage_dir().expect("storage dir"), target);
+    }
+
+    #[test]
+    fn libraries_keep_their_prompts_apart_and_can_be_switched() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Personal", "Mine")])
+            .expect("save default library");
+
+        let team_dir = test_home.root.join("Team");
+        let team = register_library("Team", &team_dir.to_string_lossy()).expect("register");
+        assert_eq!(team.id, "team");
+        assert!(!team.active);
+        assert!(matches!(
+            register_library("Again", &team_dir.to_string_lossy()),
+            Err(StorageError::Conflict { .. })
+        ));
+        assert!(matches!(
+            register_library("Relative", "relative/dir"),
+            Err(StorageError::Invalid { .. })
+        ));
+        let same_dir = test_home.root.join("Other").join("..").join("Team");
+        fs::create_dir_all(test_home.root.join("Other")).expect("create other dir");
+        let linked_dir = test_home.root.join("Linked");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&team_dir, &linked_dir).expect("link team dir");
+        for clashing in [
+            same_dir,
+            #[cfg(unix)]
+            linked_dir,
+            team_dir.join("Nested"),
+            test_home.storage_base().join("Nested"),
+            test_home.root.clone(),
+        ] {
+            assert!(
+                matches!(
+                    register_library("Clash", &clashing.to_string_lossy()),
+                    Err(StorageError::Conflict { .. })
+                ),
+                "{}",
+                clashing.display()
+            );
+        }
+        assert!(!team_dir.join("Nested").exists());
+
+        let switched = switch_library("team").expect("switch library");
+        assert_eq!(switched.root(), team_dir);
+        assert_eq!(library(), switched);
+        switched
+            .save_prompts(vec![sample_prompt("b", "Shared", "Ours")])
+            .expect("save team library");
+
+        let listed = list_libraries().expect("list libraries");
+        let ids: Vec<(&str, bool)> = listed.iter().map(|l| (l.id.as_str(), l.active)).collect();
+        assert_eq!(ids, vec![("default", false), ("team", true)]);
+        assert_eq!(switched.load_prompts().expect("load team")[0].id, "b");
+
+        assert!(matches!(
+            remove_library("default"),
+            Err(StorageError::Invalid { .. })
+        ));
+        let fallback = remove_library("team").expect("remove library");
+        assert_eq!(fallback.root(), test_home.storage_base());
+        assert_eq!(fallback.load_prompts().expect("load default")[0].id, "a");
+        assert!(team_dir.join("shared.md").is_file());
+        assert!(matches!(
+            switch_library("team"),
+            Err(StorageError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn set_storage_dir_relocates_the_active_registered_library() {
+        let test_home = TestHome::new();
+        let client_dir = test_home.root.join("Client");
+        register_library("Client", &client_dir.to_string_lossy()).expect("register");
+        switch_library("client")
+            .expect("switch library")
+            .save_prompts(vec![sample_prompt("a", "Brief", "Text")])
+            .expect("save prompts");
+
+        let moved = test_home.root.join("Client Archive");
+        let relocated = set_storage_dir(&moved.to_string_lossy(), true).expect("relocate");
+        assert_eq!(relocated.root(), moved);
+        assert!(moved.join("brief.md").is_file());
+        let listed = list_libraries().expect("list libraries");
+        assert_eq!(listed[1].path, moved.to_string_lossy());
+        assert_eq!(listed[0].path, test_home.storage_base().to_string_lossy());
+        assert!(matches!(
+            set_storage_dir(&listed[0].path, false),
+            Err(StorageError::Conflict { .. })
+        ));
+    }
+
+    #[test]
+    fn editors_are_saved_per_machine_and_validated() {
+        let _test_home = TestHome::new();
+        assert_eq!(saved_editors(), None);
+
+        let editor = |id: &str, command: &str| EditorCommand {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            command: command.to_string(),
+        };
+        let editors = vec![
+            editor("code", "code {path}"),
+            editor("nvim", "kitty nvim {path}"),
+        ];
+        set_editors(editors.clone()).expect("save editors");
+        assert_eq!(saved_editors(), Some(editors));
+
+        assert!(matches!(
+            set_editors(vec![editor("code", "  ")]),
+            Err(StorageError::Invalid { .. })
+        ));
+        assert!(matches!(
+            set_editors(vec![editor("code", "code"), editor("code", "code -n")]),
+            Err(StorageError::Conflict { .. })
+        ));
+    }
+
+    #[test]
+    fn save_and_load_roundtrip_preserves_order_and_content() {
+        let test_home = TestHome::new();
         let prompts = vec![
             sample_prompt("a", "Bug Triage", "First"),
             sample_prompt("b", "PR Review", "Second"),
         ];
 
-        save_prompts(prompts.clone()).expect("save prompts");
-        let loaded = load_prompts().expect("load prompts");
+        library()
+            .save_prompts(prompts.clone())
+            .expect("save prompts");
+        let loaded = library().load_prompts().expect("load prompts");
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].id, "a");
+        assert_eq!(loaded[0].title, "Bug Triage");
+        assert_eq!(loaded[0].content, "First");
+        assert_eq!(loaded[1].id, "b");
+        assert_eq!(loaded[1].title, "PR Review");
+        assert_eq!(loaded[1].content, "Second");
+
+        let base = test_home.storage_base();
+        assert!(base.join("bug-triage.md").exists());
+        assert!(base.join("pr-review.md").exists());
+    }
+
+    #[test]
+    fn save_and_load_roundtrip_keeps_line_endings_and_byte_order_marks() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "One\nTwo\n"),
+                sample_prompt("b", "PR Review", "Second\n"),
+                sample_prompt("c", "Release Notes", ""),
+            ])
+            .expect("save prompts");
+        let base = test_home.storage_base();
+        let rewrite = |file: &str, change: &dyn Fn(String) -> Vec<u8>| {
+            let path = base.join(file);
+            let bytes = change(fs::read_to_string(&path).expect("read prompt file"));
+            fs::write(&path, &bytes).expect("rewrite prompt file");
+            bytes
+        };
+        let crlf = rewrite("bug-triage.md", &|raw| {
+            raw.replace('\n', "\r\n").into_bytes()
+        });
+        let bom = rewrite("pr-review.md", &|raw| {
+            [&[0xEF, 0xBB, 0xBF], raw.as_bytes()].concat()
+        });
+        let no_newline = rewrite("release-notes.md", &|raw| {
+            raw.trim_end_matches('\n').as_bytes().to_vec()
+        });
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].content, "One\nTwo\n");
+        assert_eq!(loaded[1].content, "Second\n");
+        assert_eq!(loaded[2].content, "");
+        library().save_prompts(loaded).expect("save loaded prompts");
+        assert_eq!(fs::read(base.join("bug-triage.md")).expect("read"), crlf);
+        assert_eq!(fs::read(base.join("pr-review.md")).expect("read"), bom);
+        assert_eq!(
+            fs::read(base.join("release-notes.md")).expect("read"),
+            no_newline
+        );
+
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "One\nTwo\nThree\n"))
+            .expect("edit crlf prompt");
+        library()
+            .update_prompt(sample_prompt("b", "PR Review", "Edited"))
+            .expect("edit bom prompt");
+        let edited = fs::read_to_string(base.join("bug-triage.md")).expect("read");
+        assert!(edited.ends_with("\r\nOne\r\nTwo\r\nThree\r\n"));
+        assert!(!edited.replace("\r\n", "").contains('\n'));
+        let edited = fs::read(base.join("pr-review.md")).expect("read");
+        assert!(edited.starts_with(&[0xEF, 0xBB, 0xBF]));
+        assert!(edited.ends_with(b"\nEdited"));
+    }
+
+    #[test]
+    fn save_prompts_creates_unique_files_for_duplicate_titles() {
+        let _test_home = TestHome::new();
+        let prompts = vec![
+            sample_prompt("a", "Bug Triage", "First"),
+            sample_prompt("b", "Bug Triage", "Second"),
+        ];
+
+        library().save_prompts(prompts).expect("save prompts");
+
+        let base = _test_home.storage_base();
+        assert!(base.join("bug-triage.md").exists());
+        assert!(base.join("bug-triage-2.md").exists());
+    }
+
+    #[test]
+    fn save_prompts_removes_deleted_files() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+
+        let base = test_home.storage_base();
+        assert!(base.join("bug-triage.md").exists());
+        assert!(base.join("pr-review.md").exists());
+
+        library()
+            .save_prompts(vec![sample_prompt("b", "PR Review", "Second")])
+            .expect("second save");
+        assert!(!base.join("bug-triage.md").exists());
+        assert!(base.join("pr-review.md").exists());
+    }
+
+    #[test]
+    fn new_prompts_never_take_over_files_being_removed() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        library()
+            .save_prompts(vec![sample_prompt("c", "Bug Triage", "Replacement")])
+            .expect("replace prompt");
+        assert!(fs::read_to_string(base.join("bug-triage-2.md"))
+            .expect("read new prompt")
+            .ends_with("Replacement"));
+        assert!(!base.join("bug-triage.md").exists());
+        let trashed = library().list_trash().expect("list trash");
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].prompt_id, "a");
+
+        fs::write(base.join("notes.md"), "Written elsewhere").expect("write external file");
+        library()
+            .save_prompts(vec![
+                sample_prompt("c", "Bug Triage", "Replacement"),
+                sample_prompt("n", "Notes", "From the app"),
+            ])
+            .expect("save new prompt");
+        assert!(fs::read_to_string(base.join("notes-2.md"))
+            .expect("read new prompt")
+            .ends_with("From the app"));
+        assert_eq!(library().list_trash().expect("list trash").len(), 2);
+    }
+
+    #[test]
+    fn save_prompts_refuses_mass_deletion_unless_forced() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+                sample_prompt("c", "Release Notes", "Third"),
+            ])
+            .expect("initial save");
+
+        let err = library()
+            .save_prompts(vec![])
+            .expect_err("expected mass deletion error");
+        assert_eq!(
+            err,
+            StorageError::MassDeletion {
+                existing: 3,
+                removing: 3
+            }
+        );
+        let err = library()
+            .save_prompts(vec![sample_prompt("c", "Release Notes", "Edited")])
+            .expect_err("expected mass deletion error");
+        assert!(matches!(
+            err,
+            StorageError::MassDeletion { removing: 2, .. }
+        ));
+
+        let base = test_home.storage_base();
+        assert!(base.join("bug-triage.md").exists());
+        assert!(base.join("pr-review.md").exists());
+        assert!(fs::read_to_string(base.join("release-notes.md"))
+            .expect("read prompt file")
+            .ends_with("Third"));
+
+        library().force_save_prompts(vec![]).expect("forced save");
+        assert!(library().load_prompts().expect("load prompts").is_empty());
+    }
+
+    #[test]
+    fn delete_prompts_refuses_mass_deletion_unless_forced() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+                sample_prompt("c", "Release Notes", "Third"),
+            ])
+            .expect("initial save");
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        let err = library()
+            .delete_prompts(&ids(&["a", "b"]), false)
+            .expect_err("expected mass deletion error");
+        assert_eq!(
+            err,
+            StorageError::MassDeletion {
+                existing: 3,
+                removing: 2
+            }
+        );
+        assert!(test_home.storage_base().join("bug-triage.md").exists());
+
+        // Only ids the index does not know are skipped; a failed move to the
+        // trash is an error.
+        let trash = test_home.storage_base().join(".trash");
+        fs::write(&trash, "not a folder").expect("block trash");
+        assert!(library().delete_prompts(&ids(&["a"]), true).is_err());
+        assert!(test_home.storage_base().join("bug-triage.md").exists());
+        fs::remove_file(&trash).expect("unblock trash");
+
+        library()
+            .delete_prompts(&ids(&["a", "b", "gone"]), true)
+            .expect("forced delete");
+        let left: Vec<String> = library()
+            .load_prompts()
+            .expect("load prompts")
+            .into_iter()
+            .map(|prompt| prompt.id)
+            .collect();
+        assert_eq!(left, ids(&["c"]));
+    }
+
+    #[test]
+    fn save_prompts_allows_small_deletions() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        library()
+            .save_prompts(vec![])
+            .expect("deleting the only prompt is fine");
+
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+                sample_prompt("c", "Release Notes", "Third"),
+                sample_prompt("d", "Standup", "Fourth"),
+            ])
+            .expect("refill");
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("deleting half is fine");
+    }
+
+    #[test]
+    fn mass_deletion_threshold_follows_library_settings() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+
+        let mut settings = library().get_library_settings().expect("read settings");
+        settings.mass_delete.max_percent = 100;
+        library()
+            .update_library_settings(settings)
+            .expect("write settings");
+        library().save_prompts(vec![]).expect("threshold disabled");
+    }
+
+    #[test]
+    fn mass_deletion_error_serializes_as_tagged_object() {
+        let json = serde_json::to_value(StorageError::MassDeletion {
+            existing: 3,
+            removing: 2,
+        })
+        .expect("serialize");
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "massDeletion", "existing": 3, "removing": 2 })
+        );
+    }
+
+    #[test]
+    fn load_prompts_reads_markdown_without_index_metadata() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        fs::write(base.join("release-notes.md"), "Hello").expect("write prompt");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id.len(), 26);
+        assert_eq!(loaded[0].title, "Release notes");
+        assert_eq!(loaded[0].content, "Hello");
+        assert_eq!(loaded[0].copied, 0);
+        assert_eq!(loaded[0].searched, 0);
+
+        let raw = fs::read_to_string(base.join("release-notes.md")).expect("read prompt file");
+        assert!(raw.starts_with(&format!("---\nid: \"{}\"\n", loaded[0].id)));
+        assert!(raw.ends_with("---\nHello"));
+        let reloaded = library().load_prompts().expect("reload prompts");
+        assert_eq!(reloaded[0].id, loaded[0].id);
+    }
+
+    #[test]
+    fn load_prompts_gives_copied_files_ids_of_their_own() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("save prompts");
+        let base = test_home.storage_base();
+        fs::copy(base.join("bug-triage.md"), base.join("bug-triage-copy.md")).expect("copy file");
 
+        let loaded = library().load_prompts().expect("load prompts");
         assert_eq!(loaded.len(), 2);
         assert_eq!(loaded[0].id, "a");
+        let copy_id = loaded[1].id.clone();
+        assert_eq!(copy_id.len(), 26);
+        let raw = fs::read_to_string(base.join("bug-triage-copy.md")).expect("read copy");
+        assert!(raw.starts_with(&format!("---\nid: \"{copy_id}\"\n")));
+
+        let reloaded = library().load_prompts().expect("reload prompts");
+        assert_eq!(reloaded[1].id, copy_id);
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Edited"))
+            .expect("update original");
+        assert!(fs::read_to_string(base.join("bug-triage-copy.md"))
+            .expect("read copy")
+            .ends_with("---\nFirst"));
+    }
+
+    #[test]
+    fn load_prompts_keeps_ids_of_files_renamed_outside_the_app() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        fs::write(base.join("dropped.md"), "Dropped").expect("write prompt");
+        let dropped = library().load_prompts().expect("load prompts")[0].clone();
+
+        fs::create_dir_all(base.join("review")).expect("create folder");
+        fs::rename(
+            base.join("dropped.md"),
+            base.join("review").join("renamed.md"),
+        )
+        .expect("rename in file manager");
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].id, dropped.id);
+        assert_eq!(loaded[0].title, "Renamed");
+        assert_eq!(loaded[0].folder, "review");
+
+        // Indexed before ids were written to front matter: only the bytes match.
+        fs::write(base.join("legacy.md"), "Legacy").expect("write prompt");
+        let mut entries = read_index(&base).prompts;
+        entries.insert(
+            0,
+            PromptIndexEntry {
+                id: "legacy".to_string(),
+                file: "legacy.md".to_string(),
+                folder: String::new(),
+                title: "Legacy".to_string(),
+                copied: 7,
+                searched: 2,
+                tags: vec![],
+                created_at: 1,
+                updated_at: 1,
+                hash: conflicts::content_hash(b"Legacy"),
+            },
+        );
+        write_index(&base, entries).expect("write index");
+        fs::rename(base.join("legacy.md"), base.join("old-notes.md")).expect("rename");
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].id, "legacy");
+        assert_eq!(loaded[0].title, "Old notes");
+        assert_eq!(loaded[0].copied, 7);
+        assert_eq!(loaded[0].searched, 2);
+        assert_eq!(loaded[1].id, dropped.id);
+    }
+
+    #[test]
+    fn save_prompts_writes_front_matter_before_content() {
+        let test_home = TestHome::new();
+        let mut prompt = sample_prompt("a", "Bug Triage", "First");
+        prompt.tags = vec!["review".to_string()];
+        prompt.copied = 2;
+        library().save_prompts(vec![prompt]).expect("save prompts");
+
+        let raw = fs::read_to_string(test_home.storage_base().join("bug-triage.md"))
+            .expect("read prompt file");
+        assert!(raw.starts_with("---\nid: \"a\"\ntitle: \"Bug Triage\"\ntags: [\"review\"]\n"));
+        assert!(raw.contains("\ncopied: 2\n"));
+        assert!(raw.ends_with("---\nFirst"));
+    }
+
+    #[test]
+    fn load_prompts_rebuilds_metadata_from_front_matter_when_index_is_lost() {
+        let test_home = TestHome::new();
+        let mut prompt = sample_prompt("a", "Bug Triage", "First");
+        prompt.tags = vec!["review".to_string()];
+        prompt.copied = 5;
+        prompt.searched = 7;
+        library().save_prompts(vec![prompt]).expect("save prompts");
+
+        let base = test_home.storage_base();
+        fs::remove_file(index_path(&base)).expect("remove index");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, "a");
         assert_eq!(loaded[0].title, "Bug Triage");
         assert_eq!(loaded[0].content, "First");
-        assert_eq!(loaded[1].id, "b");
-        assert_eq!(loaded[1].title, "PR Review");
-        assert_eq!(loaded[1].content, "Second");
+        assert_eq!(loaded[0].tags, vec!["review".to_string()]);
+        assert_eq!(loaded[0].copied, 5);
+        assert_eq!(loaded[0].searched, 7);
+
+        let rebuilt = read_index(&base);
+        assert_eq!(rebuilt.prompts.len(), 1);
+        assert_eq!(rebuilt.prompts[0].id, "a");
+        assert_eq!(rebuilt.prompts[0].file, "bug-triage.md");
+    }
+
+    #[test]
+    fn save_prompts_preserves_created_at_and_unknown_front_matter_keys() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        fs::write(
+            base.join("notes.md"),
+            "---\nid: n\ntitle: Notes\ncreated: 42\nauthor: me\n---\nBody",
+        )
+        .expect("write prompt");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        library().save_prompts(loaded).expect("save prompts");
+
+        let raw = fs::read_to_string(base.join("notes.md")).expect("read prompt file");
+        assert!(raw.contains("\ncreated: 42\n"));
+        assert!(raw.contains("\nauthor: me\n"));
+        assert!(raw.ends_with("---\nBody"));
+    }
+
+    #[test]
+    fn save_and_load_roundtrip_preserves_folders() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt_in("review", "a", "Bug Triage", "First"),
+                sample_prompt_in("review/python", "b", "Bug Triage", "Second"),
+                sample_prompt("c", "Bug Triage", "Third"),
+            ])
+            .expect("save prompts");
 
         let base = test_home.storage_base();
+        assert!(base.join("review").join("bug-triage.md").exists());
+        assert!(base
+            .join("review")
+            .join("python")
+            .join("bug-triage.md")
+            .exists());
         assert!(base.join("bug-triage.md").exists());
-        assert!(base.join("pr-review.md").exists());
+
+        let loaded = library().load_prompts().expect("load prompts");
+        let folders: Vec<&str> = loaded.iter().map(|p| p.folder.as_str()).collect();
+        assert_eq!(folders, vec!["review", "review/python", ""]);
+        assert_eq!(loaded[1].content, "Second");
     }
 
     #[test]
-    fn save_prompts_creates_unique_files_for_duplicate_titles() {
+    fn save_prompts_moves_prompts_and_cleans_up_emptied_folders() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt_in("review", "a", "Bug Triage", "First")])
+            .expect("initial save");
+        library().create_folder("writing").expect("create folder");
+
+        library()
+            .save_prompts(vec![sample_prompt_in(
+                "debugging",
+                "a",
+                "Bug Triage",
+                "First",
+            )])
+            .expect("second save");
+
+        let base = test_home.storage_base();
+        assert!(!base.join("review").exists());
+        assert!(base.join("debugging").join("bug-triage.md").exists());
+        assert_eq!(
+            library().list_folders().expect("list folders"),
+            vec!["debugging".to_string(), "writing".to_string()]
+        );
+    }
+
+    #[test]
+    fn save_prompts_rejects_hidden_or_parent_folders() {
+        let _test_home = TestHome::new();
+        let err = library()
+            .save_prompts(vec![sample_prompt_in("../outside", "a", "Bug Triage", "")])
+            .expect_err("expected error");
+        assert!(matches!(err, StorageError::Invalid { .. }));
+        assert!(matches!(
+            library().create_folder(".history"),
+            Err(StorageError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn load_prompts_skips_hidden_folders() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(base.join(".tmp")).expect("create temp dir");
+        fs::write(base.join(".tmp").join("scratch.md"), "Temp").expect("write temp file");
+
+        assert!(library().load_prompts().expect("load prompts").is_empty());
+        assert!(library().list_folders().expect("list folders").is_empty());
+    }
+
+    #[test]
+    fn rename_folder_moves_prompts_and_keeps_metadata() {
+        let test_home = TestHome::new();
+        let mut prompt = sample_prompt_in("review", "a", "Bug Triage", "First");
+        prompt.copied = 3;
+        library().save_prompts(vec![prompt]).expect("save prompts");
+
+        library()
+            .rename_folder("review", "archive/review")
+            .expect("rename folder");
+
+        let base = test_home.storage_base();
+        assert!(!base.join("review").exists());
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].folder, "archive/review");
+        assert_eq!(loaded[0].copied, 3);
+        assert!(read_index(&base).prompts[0].file == "archive/review/bug-triage.md");
+    }
+
+    #[test]
+    fn delete_folder_refuses_folders_with_prompts() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt_in("review", "a", "Bug Triage", "First")])
+            .expect("save prompts");
+        library()
+            .create_folder("empty/nested")
+            .expect("create folder");
+
+        let err = library()
+            .delete_folder("review")
+            .expect_err("expected error");
+        assert!(matches!(err, StorageError::Conflict { .. }));
+        library()
+            .delete_folder("empty")
+            .expect("delete empty folder");
+        assert_eq!(
+            library().list_folders().expect("list folders"),
+            vec!["review".to_string()]
+        );
+    }
+
+    #[test]
+    fn delete_folder_never_removes_files_it_does_not_manage() {
+        let test_home = TestHome::new();
+        let dir = test_home.storage_base().join("assets");
+        fs::create_dir_all(dir.join(".git")).expect("create folders");
+        fs::write(dir.join("diagram.png"), "png").expect("write image");
+        fs::write(dir.join(".git").join("HEAD"), "ref").expect("write git file");
+        fs::create_dir_all(dir.join("empty")).expect("create empty folder");
+
+        let err = library()
+            .delete_folder("assets")
+            .expect_err("expected error");
+        assert!(matches!(err, StorageError::Conflict { .. }));
+        assert!(dir.join("diagram.png").is_file());
+        assert!(dir.join(".git").join("HEAD").is_file());
+    }
+
+    fn tagged_prompt(id: &str, title: &str, tags: &[&str]) -> PromptRecord {
+        PromptRecord {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..sample_prompt(id, title, "")
+        }
+    }
+
+    #[test]
+    fn save_prompts_normalizes_tags() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![tagged_prompt(
+                "a",
+                "Bug Triage",
+                &[" review ", "Review", "", "daily"],
+            )])
+            .expect("save prompts");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].tags, vec!["review", "daily"]);
+    }
+
+    #[test]
+    fn list_tags_counts_prompts_case_insensitively() {
         let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                tagged_prompt("a", "Bug Triage", &["review", "daily"]),
+                tagged_prompt("b", "PR Review", &["Review"]),
+            ])
+            .expect("save prompts");
+
+        let tags = library().list_tags().expect("list tags");
+        assert_eq!(
+            tags,
+            vec![
+                TagSummary {
+                    name: "daily".to_string(),
+                    count: 1
+                },
+                TagSummary {
+                    name: "review".to_string(),
+                    count: 2
+                },
+            ]
+        );
+
+        let with_review = library()
+            .prompts_with_tag("REVIEW")
+            .expect("prompts with tag");
+        let ids: Vec<&str> = with_review.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn rename_tag_rewrites_every_affected_prompt() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                tagged_prompt("a", "Bug Triage", &["review", "code-review"]),
+                tagged_prompt("b", "PR Review", &["review"]),
+                tagged_prompt("c", "Release Notes", &["writing"]),
+            ])
+            .expect("save prompts");
+
+        let changed = library()
+            .rename_tag("review", "code-review")
+            .expect("rename tag");
+        assert_eq!(changed, 2);
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].tags, vec!["code-review"]);
+        assert_eq!(loaded[1].tags, vec!["code-review"]);
+        assert_eq!(loaded[2].tags, vec!["writing"]);
+
+        let raw = fs::read_to_string(test_home.storage_base().join("pr-review.md"))
+            .expect("read prompt file");
+        assert!(raw.contains("tags: [\"code-review\"]"));
+        assert!(matches!(
+            library().rename_tag("writing", "  "),
+            Err(StorageError::Invalid { .. })
+        ));
+    }
+
+    fn modified(path: &Path) -> SystemTime {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .expect("read mtime")
+    }
+
+    #[test]
+    fn save_prompts_skips_unchanged_files() {
+        let test_home = TestHome::new();
         let prompts = vec![
             sample_prompt("a", "Bug Triage", "First"),
-            sample_prompt("b", "Bug Triage", "Second"),
+            sample_prompt("b", "PR Review", "Second"),
         ];
+        library()
+            .save_prompts(prompts.clone())
+            .expect("initial save");
 
-        save_prompts(prompts).expect("save prompts");
+        let base = test_home.storage_base();
+        let before_a = modified(&base.join("bug-triage.md"));
+        let before_b = modified(&base.join("pr-review.md"));
+        let before_index = modified(&index_path(&base));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        library().save_prompts(prompts).expect("second save");
+        assert_eq!(before_a, modified(&base.join("bug-triage.md")));
+        assert_eq!(before_b, modified(&base.join("pr-review.md")));
+        assert_eq!(before_index, modified(&index_path(&base)));
+    }
 
-        let base = _test_home.storage_base();
-        assert!(base.join("bug-triage.md").exists());
-        assert!(base.join("bug-triage-2.md").exists());
+    #[test]
+    fn save_prompts_only_bumps_updated_at_for_edited_prompts() {
+        let test_home = TestHome::new();
+        let base = test_home.storage_base();
+        fs::create_dir_all(&base).expect("create storage base");
+        for (file, id) in [("first.md", "a"), ("second.md", "b"), ("third.md", "c")] {
+            fs::write(
+                base.join(file),
+                format!("---\nid: {id}\ntitle: {id}\ntags: []\ncreated: 5\nupdated: 5\n---\nBody"),
+            )
+            .expect("write prompt");
+        }
+        let mut loaded = library().load_prompts().expect("load prompts");
+        loaded[0].content = "Edited".to_string();
+        loaded[1].copied = 9;
+        library().save_prompts(loaded).expect("save prompts");
+
+        let index = read_index(&base);
+        let updated: HashMap<&str, u64> = index
+            .prompts
+            .iter()
+            .map(|e| (e.id.as_str(), e.updated_at))
+            .collect();
+        assert!(updated["a"] > 5);
+        assert_eq!(updated["b"], 5);
+        assert_eq!(updated["c"], 5);
+        assert_eq!(index.prompts[1].copied, 9);
+        let raw = fs::read_to_string(base.join("second.md")).expect("read prompt file");
+        assert!(raw.contains("\nupdated: 5\ncopied: 9\n"));
     }
 
     #[test]
-    fn save_prompts_removes_deleted_files() {
+    fn create_prompt_appends_a_single_file() {
         let test_home = TestHome::new();
-        save_prompts(vec![
-            sample_prompt("a", "Bug Triage", "First"),
-            sample_prompt("b", "PR Review", "Second"),
-        ])
-        .expect("initial save");
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+
+        library()
+            .create_prompt(sample_prompt_in("review", "b", "Bug Triage", "Second"))
+            .expect("create prompt");
+        assert!(matches!(
+            library().create_prompt(sample_prompt("b", "Other", "")),
+            Err(StorageError::Conflict { .. })
+        ));
+
+        let base = test_home.storage_base();
+        assert!(base.join("review").join("bug-triage.md").exists());
+        let loaded = library().load_prompts().expect("load prompts");
+        let ids: Vec<&str> = loaded.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(loaded[1].content, "Second");
+    }
+
+    #[test]
+    fn concurrent_writers_never_lose_prompts() {
+        let test_home = TestHome::new();
+        library().save_prompts(vec![]).expect("initial save");
+
+        let handles: Vec<_> = (0..8)
+            .map(|n| {
+                let lib = library();
+                std::thread::spawn(move || {
+                    lib.create_prompt(sample_prompt(&format!("p{n}"), &format!("Prompt {n}"), ""))
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle
+                .join()
+                .expect("writer thread")
+                .expect("create prompt");
+        }
+
+        let mut ids: Vec<String> = library()
+            .load_prompts()
+            .expect("load prompts")
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, (0..8).map(|n| format!("p{n}")).collect::<Vec<_>>());
+        assert_eq!(read_index(&test_home.storage_base()).prompts.len(), 8);
+        assert!(!test_home.storage_base().join(lock::LOCK_FILE).exists());
+    }
+
+    #[test]
+    fn update_prompt_only_touches_the_affected_file() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        let base = test_home.storage_base();
+        let untouched = fs::metadata(base.join("pr-review.md"))
+            .and_then(|m| m.modified())
+            .expect("mtime");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Edited"))
+            .expect("update prompt");
+
+        let after = fs::metadata(base.join("pr-review.md"))
+            .and_then(|m| m.modified())
+            .expect("mtime");
+        assert_eq!(untouched, after);
+        let loaded = library()
+            .get_prompts(&["a".to_string()])
+            .expect("get prompts");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].content, "Edited");
+        assert!(matches!(
+            library().update_prompt(sample_prompt("missing", "Nope", "")),
+            Err(StorageError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn update_prompt_keeps_file_on_title_change_and_moves_it_with_the_folder() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
 
+        library()
+            .update_prompt(sample_prompt("a", "PR Review", "First"))
+            .expect("rename title");
         let base = test_home.storage_base();
         assert!(base.join("bug-triage.md").exists());
-        assert!(base.join("pr-review.md").exists());
+        assert!(!base.join("pr-review.md").exists());
 
-        save_prompts(vec![sample_prompt("b", "PR Review", "Second")]).expect("second save");
+        library()
+            .update_prompt(sample_prompt_in("review", "a", "PR Review", "First"))
+            .expect("move prompt");
         assert!(!base.join("bug-triage.md").exists());
-        assert!(base.join("pr-review.md").exists());
+        assert!(base.join("review").join("bug-triage.md").exists());
     }
 
     #[test]
-    fn load_prompts_reads_markdown_without_index_metadata() {
+    fn save_prompts_keeps_filenames_stable_across_title_edits_and_reorders() {
         let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "Bug Triage", "Second"),
+            ])
+            .expect("initial save");
+
+        library()
+            .save_prompts(vec![
+                sample_prompt("b", "Bug Triage", "Second"),
+                sample_prompt("a", "Renamed", "First"),
+                sample_prompt("c", "Bug Triage", "Third"),
+            ])
+            .expect("second save");
+
         let base = test_home.storage_base();
-        fs::create_dir_all(&base).expect("create storage base");
-        fs::write(base.join("release-notes.md"), "Hello").expect("write prompt");
+        let files: HashMap<String, String> = read_index(&base)
+            .prompts
+            .into_iter()
+            .map(|e| (e.id, e.file))
+            .collect();
+        assert_eq!(files["a"], "bug-triage.md");
+        assert_eq!(files["b"], "bug-triage-2.md");
+        assert_eq!(files["c"], "bug-triage-3.md");
+        let raw = fs::read_to_string(base.join("bug-triage-2.md")).expect("read prompt file");
+        assert!(raw.ends_with("Second"));
+    }
 
-        let loaded = load_prompts().expect("load prompts");
-        assert_eq!(loaded.len(), 1);
-        assert_eq!(loaded[0].id, "release-notes");
-        assert_eq!(loaded[0].title, "Release notes");
-        assert_eq!(loaded[0].content, "Hello");
-        assert_eq!(loaded[0].copied, 0);
-        assert_eq!(loaded[0].searched, 0);
+    #[test]
+    fn save_prompts_moves_files_between_folders_with_a_single_rename() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        library()
+            .update_prompt(sample_prompt("a", "Renamed", "First"))
+            .expect("rename title");
+
+        library()
+            .save_prompts(vec![sample_prompt_in("review", "a", "Renamed", "First")])
+            .expect("move prompt");
+
+        let base = test_home.storage_base();
+        assert!(!base.join("bug-triage.md").exists());
+        assert!(base.join("review").join("bug-triage.md").exists());
+    }
+
+    #[test]
+    fn rename_prompt_file_follows_the_title_on_request() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        library()
+            .update_prompt(sample_prompt("a", "PR Review", "First"))
+            .expect("rename title");
+
+        let renamed = library().rename_prompt_file("a").expect("rename file");
+        assert_eq!(renamed, "pr-review-2.md");
+        assert_eq!(
+            library().rename_prompt_file("a").expect("rename again"),
+            renamed
+        );
+
+        let base = test_home.storage_base();
+        assert!(!base.join("bug-triage.md").exists());
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].id, "a");
+        assert_eq!(loaded[0].content, "First");
+        assert!(library()
+            .get_prompt_path("a", "PR Review")
+            .expect("path by id")
+            .ends_with("pr-review-2.md"));
+    }
+
+    #[test]
+    fn delete_prompt_removes_file_and_index_entry() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt_in("review", "a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+
+        library().delete_prompt("a").expect("delete prompt");
+        assert!(matches!(
+            library().delete_prompt("a"),
+            Err(StorageError::NotFound { .. })
+        ));
+
+        let base = test_home.storage_base();
+        assert!(!base.join("review").exists());
+        let index = read_index(&base);
+        assert_eq!(index.prompts.len(), 1);
+        assert_eq!(index.prompts[0].id, "b");
+    }
+
+    #[test]
+    fn reorder_prompts_rewrites_only_the_index_order() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+                sample_prompt("c", "Release Notes", "Third"),
+            ])
+            .expect("initial save");
+
+        library()
+            .reorder_prompts(&["c".to_string(), "a".to_string()])
+            .expect("reorder prompts");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        let ids: Vec<&str> = loaded.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn saves_refuse_to_overwrite_files_changed_on_disk() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        let path = test_home.storage_base().join("bug-triage.md");
+        fs::write(&path, "Edited in vim").expect("external edit");
+
+        let err = library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Edited in app"))
+            .expect_err("update should conflict");
+        assert_eq!(
+            err,
+            StorageError::ChangedOnDisk {
+                prompt_ids: vec!["a".to_string()]
+            }
+        );
+        assert!(matches!(
+            library().save_prompts(vec![sample_prompt("a", "Bug Triage", "Edited in app")]),
+            Err(StorageError::ChangedOnDisk { .. })
+        ));
+        assert_eq!(
+            fs::read_to_string(&path).expect("read prompt"),
+            "Edited in vim"
+        );
+
+        let conflict = library().get_prompt_conflict("a").expect("get conflict");
+        assert_eq!(conflict.file, "bug-triage.md");
+        assert_eq!(conflict.local.content, "Edited in app");
+        assert_eq!(conflict.disk.content, "Edited in vim");
+
+        let mut merged = conflict.local;
+        merged.content = "Edited in vim and app".to_string();
+        library()
+            .resolve_prompt_conflict(merged)
+            .expect("resolve conflict");
+        assert!(fs::read_to_string(&path)
+            .expect("read prompt")
+            .ends_with("Edited in vim and app"));
+        assert!(library().get_prompt_conflict("a").is_err());
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Later edit"))
+            .expect("later update");
+    }
+
+    #[test]
+    fn loading_a_prompt_accepts_its_external_edits() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        let base = test_home.storage_base();
+        fs::write(base.join("bug-triage.md"), "From git").expect("external edit");
+        fs::write(base.join("pr-review.md"), "Also from git").expect("external edit");
+
+        let fetched = library()
+            .get_prompts(&["a".to_string()])
+            .expect("get prompts");
+        assert_eq!(fetched[0].content, "From git");
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Then edited"))
+            .expect("update after get_prompts");
+
+        library().load_prompts().expect("load prompts");
+        library()
+            .update_prompt(sample_prompt("b", "PR Review", "Then edited"))
+            .expect("update after load_prompts");
+    }
+
+    #[test]
+    fn changed_externally_ignores_the_apps_own_writes() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+                sample_prompt("c", "Release Notes", "Third"),
+            ])
+            .expect("initial save");
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Edited in app"))
+            .expect("update prompt");
+        library().delete_prompt("c").expect("delete prompt");
+
+        let base = test_home.storage_base();
+        fs::write(base.join("pr-review.md"), "Edited in vim").expect("external edit");
+        fs::write(base.join("notes.md"), "New").expect("external create");
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            library()
+                .changed_externally(files(&[
+                    "bug-triage.md",
+                    "pr-review.md",
+                    "release-notes.md",
+                    "notes.md"
+                ]))
+                .expect("check files"),
+            files(&["pr-review.md", "notes.md"])
+        );
+
+        fs::remove_file(base.join("bug-triage.md")).expect("external delete");
+        assert_eq!(
+            library()
+                .changed_externally(files(&["bug-triage.md"]))
+                .expect("check files"),
+            files(&["bug-triage.md"])
+        );
+    }
+
+    #[test]
+    fn changed_externally_reports_folders_moved_outside_the_app() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt_in("review", "a", "Bug Triage", "First"),
+                sample_prompt_in("drafts", "b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        library()
+            .rename_folder("review", "reviews")
+            .expect("rename in app");
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert!(library()
+            .changed_externally(files(&["review", "reviews", "drafts"]))
+            .expect("check folders")
+            .is_empty());
+
+        let base = test_home.storage_base();
+        fs::rename(base.join("reviews"), base.join("archive")).expect("external rename");
+        fs::remove_dir_all(base.join("drafts")).expect("external delete");
+        fs::create_dir_all(base.join("ideas")).expect("external create");
+        assert_eq!(
+            library()
+                .changed_externally(files(&["reviews", "archive", "drafts", "ideas"]))
+                .expect("check folders"),
+            files(&["reviews", "archive", "drafts", "ideas"])
+        );
+    }
+
+    #[test]
+    fn edits_keep_the_encoding_a_file_was_written_in() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        let base = test_home.storage_base();
+        fs::write(base.join("bug-triage.md"), b"Caf\xe9 au lait").expect("write latin-1");
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("Naïve".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        fs::write(base.join("pr-review.md"), utf16).expect("write utf-16");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].content, "Café au lait");
+        assert_eq!(loaded[1].content, "Naïve");
+        assert!(loaded.iter().all(|p| p.load_error.is_none()));
+
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Café noir"))
+            .expect("update latin-1 prompt");
+        library()
+            .update_prompt(sample_prompt("b", "PR Review", "Naïve — edited"))
+            .expect("update utf-16 prompt");
+        let latin1 = fs::read(base.join("bug-triage.md")).expect("read latin-1");
+        assert!(latin1.ends_with(b"Caf\xe9 noir"));
+        let utf16 = fs::read(base.join("pr-review.md")).expect("read utf-16");
+        assert!(utf16.starts_with(&[0xFF, 0xFE]));
+        let reloaded = library().load_prompts().expect("reload prompts");
+        assert_eq!(reloaded[0].content, "Café noir");
+        assert_eq!(reloaded[1].content, "Naïve — edited");
+    }
+
+    #[test]
+    fn utf16_files_without_a_bom_round_trip_unchanged() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        let path = test_home.storage_base().join("bug-triage.md");
+        let raw = fs::read_to_string(&path).expect("read prompt file");
+        let utf16: Vec<u8> = raw.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        fs::write(&path, &utf16).expect("write utf-16");
+
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].content, "First");
+        library().save_prompts(loaded).expect("save loaded prompts");
+        assert_eq!(fs::read(&path).expect("read utf-16"), utf16);
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", "Edited"))
+            .expect("edit prompt");
+        let edited = fs::read(&path).expect("read utf-16");
+        assert!(!edited.starts_with(&[0xFF, 0xFE]));
+        assert!(edited.ends_with(&[b'E', 0, b'd', 0, b'i', 0, b't', 0, b'e', 0, b'd', 0]));
+    }
+
+    #[test]
+    fn files_that_are_not_text_are_never_written_over() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        let path = test_home.storage_base().join("bug-triage.md");
+        let binary = [0x89, b'P', b'N', b'G', 0, 0, 0, 0x0D, 0x49];
+        fs::write(&path, binary).expect("write binary");
+
+        let mut loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded[0].content, "");
+        assert!(loaded[0].load_error.is_some());
+        assert!(loaded[1].load_error.is_none());
+
+        loaded[1].content = "Second, edited".to_string();
+        library()
+            .save_prompts(loaded)
+            .expect("save around unreadable file");
+        assert!(matches!(
+            library().update_prompt(sample_prompt("a", "Bug Triage", "")),
+            Err(StorageError::Unreadable { .. })
+        ));
+        assert!(matches!(
+            library().update_prompt(sample_prompt("a", "Renamed", "")),
+            Err(StorageError::Unreadable { .. })
+        ));
+        assert_eq!(fs::read(&path).expect("read binary"), binary);
+        let reloaded = library().load_prompts().expect("reload prompts");
+        assert_eq!(reloaded[0].id, "a");
+        assert_eq!(reloaded[1].content, "Second, edited");
+    }
+
+    #[test]
+    fn saving_changed_content_records_a_revision() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        assert!(library()
+            .list_prompt_revisions("a")
+            .expect("list revisions")
+            .is_empty());
+
+        let mut counted = sample_prompt("a", "Bug Triage", "First");
+        counted.copied = 1;
+        library().save_prompts(vec![counted]).expect("counter save");
+        assert!(library()
+            .list_prompt_revisions("a")
+            .expect("list revisions")
+            .is_empty());
+
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "")])
+            .expect("wipe content");
+        let revisions = library()
+            .list_prompt_revisions("a")
+            .expect("list revisions");
+        assert_eq!(revisions.len(), 1);
+
+        let revision = library()
+            .get_prompt_revision("a", &revisions[0].id)
+            .expect("get revision");
+        assert_eq!(revision.content, "First");
+        assert_eq!(revision.title.as_deref(), Some("Bug Triage"));
+    }
+
+    #[test]
+    fn wiping_a_file_without_front_matter_records_a_revision() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        fs::write(
+            test_home.storage_base().join("bug-triage.md"),
+            "Written long ago",
+        )
+        .expect("write old-style file");
+        library().load_prompts().expect("load prompts");
+
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "")])
+            .expect("wipe content");
+        let revisions = library()
+            .list_prompt_revisions("a")
+            .expect("list revisions");
+        assert_eq!(revisions.len(), 1);
+        let revision = library()
+            .get_prompt_revision("a", &revisions[0].id)
+            .expect("get revision");
+        assert_eq!(revision.content, "Written long ago");
+    }
+
+    #[test]
+    fn restore_prompt_revision_brings_content_back_and_keeps_history() {
+        let _test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        library()
+            .update_prompt(sample_prompt("a", "Bug Triage", ""))
+            .expect("wipe content");
+        let revision = library()
+            .list_prompt_revisions("a")
+            .expect("list revisions")[0]
+            .clone();
+
+        let restored = library()
+            .restore_prompt_revision("a", &revision.id)
+            .expect("restore revision");
+        assert_eq!(restored.content, "First");
+        assert_eq!(
+            library().load_prompts().expect("load prompts")[0].content,
+            "First"
+        );
+
+        let revisions = library()
+            .list_prompt_revisions("a")
+            .expect("list revisions");
+        assert_eq!(revisions.len(), 2);
+        let wiped = library()
+            .get_prompt_revision("a", &revisions[0].id)
+            .expect("get revision");
+        assert_eq!(wiped.content, "");
+    }
+
+    #[test]
+    fn history_retention_follows_library_settings() {
+        let _test_home = TestHome::new();
+        let mut settings = library().get_library_settings().expect("read settings");
+        settings.history.max_revisions = 0;
+        library()
+            .update_library_settings(settings.clone())
+            .expect("write settings");
+        assert_eq!(
+            library().get_library_settings().expect("read settings"),
+            settings
+        );
+
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("initial save");
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "Second")])
+            .expect("second save");
+        assert!(library()
+            .list_prompt_revisions("a")
+            .expect("list revisions")
+            .is_empty());
+    }
+
+    #[test]
+    fn deleted_prompts_move_to_trash_with_metadata() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt_in("review", "a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+
+        library()
+            .save_prompts(vec![sample_prompt("b", "PR Review", "Second")])
+            .expect("drop a");
+        library().delete_prompt("b").expect("delete b");
+
+        let trashed = library().list_trash().expect("list trash");
+        assert_eq!(trashed.len(), 2);
+        assert_eq!(trashed[0].prompt_id, "b");
+        assert_eq!(trashed[1].prompt_id, "a");
+        assert_eq!(trashed[1].title, "Bug Triage");
+        assert_eq!(trashed[1].file, "review/bug-triage.md");
+        assert!(library().load_prompts().expect("load prompts").is_empty());
+        assert!(!test_home.storage_base().join("review").exists());
+    }
+
+    #[test]
+    fn restore_from_trash_brings_back_file_and_metadata() {
+        let test_home = TestHome::new();
+        let mut prompt = sample_prompt_in("review", "a", "Bug Triage", "First");
+        prompt.copied = 4;
+        library().save_prompts(vec![prompt]).expect("initial save");
+        library().delete_prompt("a").expect("delete prompt");
+        let trash_id = library().list_trash().expect("list trash")[0].id.clone();
+
+        let restored = library().restore_from_trash(&trash_id).expect("restore");
+        assert_eq!(restored.id, "a");
+        assert_eq!(restored.copied, 4);
+        assert_eq!(restored.folder, "review");
+        assert!(test_home
+            .storage_base()
+            .join("review")
+            .join("bug-triage.md")
+            .exists());
+        assert!(library().list_trash().expect("list trash").is_empty());
+        assert_eq!(
+            library().load_prompts().expect("load prompts")[0].content,
+            "First"
+        );
+        assert!(matches!(
+            library().restore_from_trash(&trash_id),
+            Err(StorageError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_and_purge_trash_remove_items() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![
+                sample_prompt("a", "Bug Triage", "First"),
+                sample_prompt("b", "PR Review", "Second"),
+            ])
+            .expect("initial save");
+        library().force_save_prompts(vec![]).expect("delete all");
+        assert_eq!(library().list_trash().expect("list trash").len(), 2);
+
+        let base = test_home.storage_base();
+        let old = &library().list_trash().expect("list trash")[0];
+        let meta_path = base.join(".trash").join(&old.id).join("meta.json");
+        let mut aged = old.clone();
+        aged.deleted_at = 1;
+        fs::write(&meta_path, serde_json::to_string(&aged).expect("serialize"))
+            .expect("age trash item");
+
+        assert_eq!(library().purge_expired_trash().expect("purge"), 1);
+        assert_eq!(library().list_trash().expect("list trash").len(), 1);
+        assert_eq!(library().empty_trash().expect("empty trash"), 1);
+        assert!(library().list_trash().expect("list trash").is_empty());
+    }
+
+    #[test]
+    fn purging_a_prompt_drops_its_history_once_nothing_refers_to_it() {
+        let _test_home = TestHome::new();
+        for id in ["a", "b", "c"] {
+            library()
+                .save_prompts(vec![sample_prompt(id, id, "First")])
+                .expect("initial save");
+            library()
+                .save_prompts(vec![sample_prompt(id, id, "Second")])
+                .expect("edit");
+        }
+        library()
+            .save_prompts(vec![sample_prompt("c", "c", "Third")])
+            .expect("keep c");
+        for id in ["a", "b"] {
+            assert!(!library()
+                .list_prompt_revisions(id)
+                .expect("revisions")
+                .is_empty());
+        }
+
+        library().delete_prompt("c").expect("delete c");
+        library()
+            .save_prompts(vec![sample_prompt("c", "c", "Back")])
+            .expect("recreate c");
+        library().empty_trash().expect("empty trash");
+
+        assert!(library()
+            .list_prompt_revisions("a")
+            .expect("revisions")
+            .is_empty());
+        assert!(library()
+            .list_prompt_revisions("b")
+            .expect("revisions")
+            .is_empty());
+        assert!(!library()
+            .list_prompt_revisions("c")
+            .expect("revisions")
+            .is_empty());
     }
 
     #[test]
     fn get_prompt_path_prefers_index_mapping_and_falls_back_to_slug() {
         let _test_home = TestHome::new();
-        save_prompts(vec![sample_prompt("a", "Bug Triage", "First")]).expect("save prompts");
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("save prompts");
 
-        let by_id = get_prompt_path("a", "Ignored").expect("path by id");
+        let by_id = library()
+            .get_prompt_path("a", "Ignored")
+            .expect("path by id");
         assert!(by_id.ends_with("bug-triage.md"));
 
-        let by_slug = get_prompt_path("missing-id", "Bug Triage").expect("path by slug");
+        let by_slug = library()
+            .get_prompt_path("missing-id", "Bug Triage")
+            .expect("path by slug");
         assert!(by_slug.ends_with("bug-triage.md"));
     }
 
+    #[test]
+    fn index_entries_cannot_reach_outside_the_library() {
+        let test_home = TestHome::new();
+        library()
+            .save_prompts(vec![sample_prompt("a", "Bug Triage", "First")])
+            .expect("save prompts");
+        let base = test_home.storage_base();
+        let outside = base.parent().expect("test home").join("outside.md");
+        fs::write(&outside, "Secret").expect("write outside file");
+        let hostile = |id: &str, file: &str| PromptIndexEntry {
+            id: id.to_string(),
+            file: file.to_string(),
+            folder: String::new(),
+            title: id.to_string(),
+            copied: 0,
+            searched: 0,
+            tags: vec![],
+            created_at: 0,
+            updated_at: 0,
+            hash: String::new(),
+        };
+        let mut entries = read_index(&base).prompts;
+        entries.push(hostile("up", "../outside.md"));
+        entries.push(hostile("absolute", &outside.to_string_lossy()));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, base.join("link.md")).expect("symlink file");
+            std::os::unix::fs::symlink(base.parent().expect("test home"), base.join("escape"))
+                .expect("symlink folder");
+            entries.push(hostile("link", "link.md"));
+            entries.push(hostile("escape", "escape/outside.md"));
+        }
+        write_index(&base, entries).expect("write hostile index");
+
+        for id in ["up", "absolute", "link", "escape"] {
+            assert!(matches!(
+                library().get_prompt_path(id, "Link"),
+                Err(StorageError::NotFound { .. })
+            ));
+            assert!(library()
+                .get_prompts(&[id.to_string()])
+                .expect("get")
+                .is_empty());
+        }
+        let loaded = library().load_prompts().expect("load prompts");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, "a");
+        assert!(read_index(&base).prompts.iter().all(|e| e.id == "a"));
+        assert_eq!(
+            library()
+                .changed_externally(vec!["../outside.md".to_string()])
+                .expect("check files"),
+            Vec::<String>::new()
+        );
+
+        let mut renamed = sample_prompt("up", "Stolen", "Overwritten");
+        renamed.folder = "review".to_string();
+        library()
+            .update_prompt(renamed)
+            .expect_err("unknown prompt");
+        library().delete_prompt("up").expect_err("unknown prompt");
+        assert_eq!(
+            fs::read_to_string(&outside).expect("read outside"),
+            "Secret"
+        );
+    }
+
     #[test]
     fn get_prompt_path_errors_when_prompt_does_not_exist() {
         let _test_home = TestHome::new();
-        let err = get_prompt_path("missing", "Unknown Title").expect_err("expected error");
-        assert!(err.contains("Prompt file not found yet"));
+        let err = library()
+            .get_prompt_path("missing", "Unknown Title")
+            .expect_err("expected error");
+        assert!(matches!(err, StorageError::NotFound { .. }));
     }
 
     #[test]
@@ -466,7 +3621,7 @@ mod tests {
         let previous_home = env::var("HOME").ok();
         env::remove_var("HOME");
 
-        let result = load_prompts();
+        let result = active_library().and_then(|library| library.load_prompts());
 
         if let Some(ref prev) = previous_home {
             env::set_var("HOME", prev);
@@ -474,6 +3629,9 @@ mod tests {
         drop(lock);
 
         assert!(result.is_err());
-        assert_eq!(result.expect_err("expected error"), "HOME is not set");
+        assert_eq!(
+            result.expect_err("expected error"),
+            StorageError::HomeMissing
+        );
     }
 }
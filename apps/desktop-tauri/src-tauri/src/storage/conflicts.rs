@@ -0,0 +1,72 @@
+//! Catches prompt files that were edited outside the app (in an editor, by a
+//! `git pull`, ...) before a save overwrites them.
+//!
+//! The index remembers a hash of every file as the app last read or wrote
+//! it. A save that finds a different hash on disk is refused, and the version
+//! it tried to write is parked in `.conflicts/` until the user picks one.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{history, PromptRecord, StorageError};
+
+/// Both sides of a refused save.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptConflict {
+    pub prompt_id: String,
+    pub file: String,
+    /// What the app tried to save.
+    pub local: PromptRecord,
+    /// What is on disk now.
+    pub disk: PromptRecord,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is guaranteed to give the same
+/// result in every release, so hashes stored in the index stay comparable.
+pub(super) fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn stash_path(base: &Path, prompt_id: &str) -> PathBuf {
+    base.join(".conflicts")
+        .join(format!("{}.json", history::encode_id(prompt_id)))
+}
+
+pub(super) fn stash(base: &Path, prompt: &PromptRecord) -> Result<(), StorageError> {
+    let path = stash_path(base, &prompt.id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| StorageError::io("create conflicts dir", e))?;
+    }
+    let json = serde_json::to_vec_pretty(prompt)
+        .map_err(|e| StorageError::json("serialize conflicting prompt", e))?;
+    super::atomic_write(&path, &json)
+}
+
+pub(super) fn stashed(base: &Path, prompt_id: &str) -> Option<PromptRecord> {
+    fs::read_to_string(stash_path(base, prompt_id))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+pub(super) fn clear(base: &Path, prompt_id: &str) {
+    let _ = fs::remove_file(stash_path(base, prompt_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_is_stable_and_sensitive_to_every_byte() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash(b"hello\n"), content_hash(b"hello\r\n"));
+    }
+}
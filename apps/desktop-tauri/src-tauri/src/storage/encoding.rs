@@ -0,0 +1,191 @@
+//! Reads prompt files that other tools wrote in something other than plain
+//! UTF-8, and writes them back the way they came.
+//!
+//! Byte order marks are trusted first. Without one, a file with NUL bytes is
+//! read as UTF-16 if it looks like it, and is otherwise treated as binary
+//! and left alone. Anything else that is not valid UTF-8 is read as Latin-1,
+//! which maps every byte to a character and back.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) enum Charset {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+/// How a file was written. Whether it started with a byte order mark is kept
+/// apart from the charset, since UTF-8 and UTF-16 files come both ways.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct Encoding {
+    pub charset: Charset,
+    pub bom: bool,
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+fn with_bom(charset: Charset) -> Encoding {
+    Encoding { charset, bom: true }
+}
+
+fn without_bom(charset: Charset) -> Encoding {
+    Encoding {
+        charset,
+        bom: false,
+    }
+}
+
+/// The text of `bytes` and how it was encoded, or `None` if it cannot be
+/// read as text.
+pub(super) fn decode(bytes: &[u8]) -> Option<(String, Encoding)> {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        return String::from_utf8(rest.to_vec())
+            .ok()
+            .map(|text| (text, with_bom(Charset::Utf8)));
+    }
+    if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        return decode_utf16(rest, u16::from_le_bytes)
+            .map(|text| (text, with_bom(Charset::Utf16Le)));
+    }
+    if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        return decode_utf16(rest, u16::from_be_bytes)
+            .map(|text| (text, with_bom(Charset::Utf16Be)));
+    }
+    if bytes.contains(&0) {
+        return match utf16_without_bom(bytes) {
+            Some(Charset::Utf16Le) => decode_utf16(bytes, u16::from_le_bytes)
+                .map(|text| (text, without_bom(Charset::Utf16Le))),
+            Some(Charset::Utf16Be) => decode_utf16(bytes, u16::from_be_bytes)
+                .map(|text| (text, without_bom(Charset::Utf16Be))),
+            _ => None,
+        };
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some((text.to_string(), without_bom(Charset::Utf8))),
+        Err(_) => Some((
+            bytes.iter().map(|&b| b as char).collect(),
+            without_bom(Charset::Latin1),
+        )),
+    }
+}
+
+/// Encodes `text` the way the file it came from was. Latin-1 text that
+/// gained characters Latin-1 cannot hold is written as UTF-8 instead.
+pub(super) fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+    let (bom, body): (&[u8], Vec<u8>) = match encoding.charset {
+        Charset::Utf8 => (UTF8_BOM, text.as_bytes().to_vec()),
+        Charset::Utf16Le => (
+            UTF16_LE_BOM,
+            text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        ),
+        Charset::Utf16Be => (
+            UTF16_BE_BOM,
+            text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        ),
+        Charset::Latin1 if text.chars().all(|c| (c as u32) < 0x100) => {
+            (&[], text.chars().map(|c| c as u8).collect())
+        }
+        Charset::Latin1 => (&[], text.as_bytes().to_vec()),
+    };
+    if encoding.bom {
+        [bom, &body].concat()
+    } else {
+        body
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .ok()
+}
+
+/// Mostly-ASCII UTF-16 has a zero in every other byte; which half holds the
+/// zeros gives away the byte order.
+fn utf16_without_bom(bytes: &[u8]) -> Option<Charset> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let zeros_at = |offset: usize| {
+        bytes
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    if zeros_at(1) * 2 > pairs && zeros_at(0) == 0 {
+        Some(Charset::Utf16Le)
+    } else if zeros_at(0) * 2 > pairs && zeros_at(1) == 0 {
+        Some(Charset::Utf16Be)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_and_re_encodes_every_supported_encoding() {
+        let text = "Café — naïve\n";
+        for charset in [Charset::Utf8, Charset::Utf16Le, Charset::Utf16Be] {
+            for encoding in [with_bom(charset), without_bom(charset)] {
+                let bytes = encode(text, encoding);
+                assert_eq!(decode(&bytes), Some((text.to_string(), encoding)));
+                let (decoded, found) = decode(&bytes).expect("decode");
+                assert_eq!(encode(&decoded, found), bytes);
+            }
+        }
+
+        let latin1 = b"Caf\xe9 na\xefve\n";
+        let (decoded, encoding) = decode(latin1).expect("decode latin-1");
+        assert_eq!(decoded, "Café naïve\n");
+        assert_eq!(encoding, without_bom(Charset::Latin1));
+        assert_eq!(encode(&decoded, encoding), latin1.to_vec());
+        assert_eq!(
+            encode("€", without_bom(Charset::Latin1)),
+            "€".as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn detects_utf16_without_a_bom() {
+        let le: Vec<u8> = "Hi there"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            decode(&le),
+            Some(("Hi there".to_string(), without_bom(Charset::Utf16Le)))
+        );
+        let be: Vec<u8> = "Hi there"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(
+            decode(&be),
+            Some(("Hi there".to_string(), without_bom(Charset::Utf16Be)))
+        );
+        assert_eq!(encode("Hi there", without_bom(Charset::Utf16Le)), le);
+    }
+    #[test]
+    fn refuses_binary_and_broken_utf16() {
+        assert_eq!(decode(&[0x89, b'P', b'N', b'G', 0, 0, 0, 0x0D, 0x49]), None);
+        // Odd length after the BOM.
+        assert_eq!(decode(&[0xFF, 0xFE, b'a', 0, b'b']), None);
+        // Unpaired surrogate.
+        assert_eq!(decode(&[0xFF, 0xFE, 0x00, 0xD8, b'a', 0]), None);
+        // A UTF-8 BOM in front of bytes that are not UTF-8.
+        assert_eq!(decode(&[0xEF, 0xBB, 0xBF, 0xE9]), None);
+    }
+}
@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+
+/// Everything storage can fail with, tagged by `kind` when sent to the UI so
+/// it can offer a fix (free up space, pick another folder, ...) instead of
+/// only printing the message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum StorageError {
+    NotFound {
+        message: String,
+    },
+    PermissionDenied {
+        message: String,
+    },
+    DiskFull {
+        message: String,
+    },
+    /// The operation clashes with what is already there: a name that is
+    /// taken, a folder that is not empty, and so on.
+    Conflict {
+        message: String,
+    },
+    CorruptIndex {
+        message: String,
+    },
+    HomeMissing,
+    Invalid {
+        message: String,
+    },
+    /// A prompt file that is not text PromptBook can decode. It is never
+    /// written to, so nothing in it is lost.
+    Unreadable {
+        message: String,
+    },
+    /// Another window or process is writing to the library.
+    Locked {
+        message: String,
+    },
+    /// Prompt files that were edited outside the app since it last read them.
+    /// Nothing was written; `get_prompt_conflict` shows both versions.
+    ChangedOnDisk {
+        #[serde(rename = "promptIds")]
+        prompt_ids: Vec<String>,
+    },
+    /// An external editor could not be started: a bad command template, an
+    /// unset `$VISUAL`, a program that is not installed.
+    Editor {
+        message: String,
+    },
+    /// A save that would delete most of the library; resend with `force`.
+    MassDeletion {
+        existing: usize,
+        removing: usize,
+    },
+    Io {
+        message: String,
+    },
+}
+
+impl StorageError {
+    pub(super) fn not_found(message: impl Into<String>) -> Self {
+        StorageError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub(super) fn conflict(message: impl Into<String>) -> Self {
+        StorageError::Conflict {
+            message: message.into(),
+        }
+    }
+
+    pub(super) fn invalid(message: impl Into<String>) -> Self {
+        StorageError::Invalid {
+            message: message.into(),
+        }
+    }
+
+    /// Wraps an I/O failure, keeping the kinds the UI can act on.
+    pub(super) fn io(context: impl fmt::Display, err: io::Error) -> Self {
+        let message = format!("{}: {}", context, err);
+        match err.kind() {
+            io::ErrorKind::NotFound => StorageError::NotFound { message },
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                StorageError::PermissionDenied { message }
+            }
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => {
+                StorageError::DiskFull { message }
+            }
+            _ => StorageError::Io { message },
+        }
+    }
+
+    pub(super) fn json(context: impl fmt::Display, err: serde_json::Error) -> Self {
+        StorageError::Io {
+            message: format!("{}: {}", context, err),
+        }
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound { message }
+            | StorageError::PermissionDenied { message }
+            | StorageError::DiskFull { message }
+            | StorageError::Conflict { message }
+            | StorageError::CorruptIndex { message }
+            | StorageError::Invalid { message }
+            | StorageError::Unreadable { message }
+            | StorageError::Locked { message }
+            | StorageError::Editor { message }
+            | StorageError::Io { message } => f.write_str(message),
+            StorageError::HomeMissing => f.write_str("HOME is not set"),
+            StorageError::ChangedOnDisk { prompt_ids } => write!(
+                f,
+                "Changed outside PromptBook since it was loaded: {}",
+                prompt_ids.join(", ")
+            ),
+            StorageError::MassDeletion { existing, removing } => write!(
+                f,
+                "Refusing to delete {} of {} prompts in one save.",
+                removing, existing
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_keep_actionable_kinds() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            StorageError::io("write a.md", denied),
+            StorageError::PermissionDenied { .. }
+        ));
+        let full = io::Error::from(io::ErrorKind::StorageFull);
+        assert!(matches!(
+            StorageError::io("write a.md", full),
+            StorageError::DiskFull { .. }
+        ));
+        let other = io::Error::other("boom");
+        assert_eq!(
+            StorageError::io("write a.md", other).to_string(),
+            "write a.md: boom"
+        );
+    }
+
+    #[test]
+    fn serializes_as_tagged_object() {
+        let json = serde_json::to_value(StorageError::not_found("Prompt not found: a"))
+            .expect("serialize");
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "notFound", "message": "Prompt not found: a" })
+        );
+        let json = serde_json::to_value(StorageError::HomeMissing).expect("serialize");
+        assert_eq!(json, serde_json::json!({ "kind": "homeMissing" }));
+    }
+}
@@ -0,0 +1,231 @@
+//! Minimal YAML front matter support for prompt files.
+//!
+//! Only the flat subset we write ourselves is understood: `key: value` pairs,
+//! inline `[a, b]` lists and `- item` block lists. Keys we do not know about
+//! are kept verbatim so hand-written metadata survives a save.
+
+const DELIMITER: &str = "---";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct FrontMatter {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub created: Option<u64>,
+    pub updated: Option<u64>,
+    pub copied: Option<u32>,
+    pub searched: Option<u32>,
+    pub extra: Vec<String>,
+}
+
+/// Splits a prompt file into its front matter and body. Files without a
+/// well-formed block are returned untouched as body.
+pub(super) fn split(raw: &str) -> (Option<FrontMatter>, &str) {
+    let Some(rest) = strip_line(raw, DELIMITER) else {
+        return (None, raw);
+    };
+
+    let mut offset = 0;
+    let mut lines = vec![];
+    loop {
+        let remaining = &rest[offset..];
+        if remaining.is_empty() {
+            return (None, raw);
+        }
+        let (line, consumed) = match remaining.find('\n') {
+            Some(i) => (&remaining[..i], i + 1),
+            None => (remaining, remaining.len()),
+        };
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        offset += consumed;
+        if line == DELIMITER {
+            break;
+        }
+        lines.push(line);
+    }
+
+    match parse(&lines) {
+        Some(meta) => (Some(meta), &rest[offset..]),
+        None => (None, raw),
+    }
+}
+
+pub(super) fn render(meta: &FrontMatter, body: &str) -> String {
+    let mut out = String::from("---\n");
+    if let Some(id) = &meta.id {
+        out.push_str(&format!("id: {}\n", quote(id)));
+    }
+    if let Some(title) = &meta.title {
+        out.push_str(&format!("title: {}\n", quote(title)));
+    }
+    if let Some(tags) = &meta.tags {
+        let items: Vec<String> = tags.iter().map(|t| quote(t)).collect();
+        out.push_str(&format!("tags: [{}]\n", items.join(", ")));
+    }
+    if let Some(created) = meta.created {
+        out.push_str(&format!("created: {created}\n"));
+    }
+    if let Some(updated) = meta.updated {
+        out.push_str(&format!("updated: {updated}\n"));
+    }
+    if let Some(copied) = meta.copied {
+        out.push_str(&format!("copied: {copied}\n"));
+    }
+    if let Some(searched) = meta.searched {
+        out.push_str(&format!("searched: {searched}\n"));
+    }
+    for line in &meta.extra {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str("---\n");
+    out.push_str(body);
+    out
+}
+
+fn strip_line<'a>(raw: &'a str, expected: &str) -> Option<&'a str> {
+    let rest = raw.strip_prefix(expected)?;
+    rest.strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+}
+
+fn parse(lines: &[&str]) -> Option<FrontMatter> {
+    let mut meta = FrontMatter::default();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            meta.extra.push(line.to_string());
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) || trimmed.starts_with('-') {
+            // Continuation of an unknown key's nested value.
+            meta.extra.last()?;
+            meta.extra.push(line.to_string());
+            continue;
+        }
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        let value = value.trim();
+
+        let mut block_items = vec![];
+        if value.is_empty() {
+            while let Some(item) = lines.get(i).and_then(|l| l.trim().strip_prefix('-')) {
+                block_items.push(scalar(item.trim()));
+                i += 1;
+            }
+        }
+
+        match key {
+            "id" => meta.id = Some(scalar(value)),
+            "title" => meta.title = Some(scalar(value)),
+            "tags" if value.is_empty() => meta.tags = Some(block_items),
+            "tags" => meta.tags = Some(list(value)),
+            "created" => meta.created = value.parse().ok(),
+            "updated" => meta.updated = value.parse().ok(),
+            "copied" => meta.copied = value.parse().ok(),
+            "searched" => meta.searched = value.parse().ok(),
+            _ => {
+                meta.extra.push(line.to_string());
+                let start = i - block_items.len();
+                meta.extra
+                    .extend(lines[start..i].iter().map(|l| l.to_string()));
+            }
+        }
+    }
+    Some(meta)
+}
+
+fn scalar(value: &str) -> String {
+    if value.starts_with('"') {
+        if let Ok(parsed) = serde_json::from_str::<String>(value) {
+            return parsed;
+        }
+    }
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+    value.to_string()
+}
+
+fn list(value: &str) -> Vec<String> {
+    if let Ok(parsed) = serde_json::from_str::<Vec<String>>(value) {
+        return parsed;
+    }
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    inner
+        .split(',')
+        .map(|item| scalar(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FrontMatter {
+        FrontMatter {
+            id: Some("a".to_string()),
+            title: Some("Bug: \"Triage\"".to_string()),
+            tags: Some(vec!["review".to_string(), "daily".to_string()]),
+            created: Some(10),
+            updated: Some(20),
+            copied: Some(3),
+            searched: Some(4),
+            extra: vec![],
+        }
+    }
+
+    #[test]
+    fn render_and_split_roundtrip() {
+        let raw = render(&sample(), "Body\n---\nmore");
+        let (meta, body) = split(&raw);
+        assert_eq!(meta, Some(sample()));
+        assert_eq!(body, "Body\n---\nmore");
+    }
+
+    #[test]
+    fn split_without_front_matter_returns_raw_body() {
+        assert_eq!(split("Hello"), (None, "Hello"));
+        assert_eq!(split("---\nunterminated"), (None, "---\nunterminated"));
+        assert_eq!(
+            split("---\nnot yaml at all\n---\nBody"),
+            (None, "---\nnot yaml at all\n---\nBody")
+        );
+    }
+
+    #[test]
+    fn split_reads_hand_written_yaml() {
+        let raw = "---\r\ntitle: Release notes\ntags:\n  - writing\n  - 'team''s'\nauthor: me\n---\r\nBody";
+        let (meta, body) = split(raw);
+        let meta = meta.expect("front matter");
+        assert_eq!(meta.title.as_deref(), Some("Release notes"));
+        assert_eq!(
+            meta.tags,
+            Some(vec!["writing".to_string(), "team's".to_string()])
+        );
+        assert_eq!(meta.id, None);
+        assert_eq!(meta.extra, vec!["author: me".to_string()]);
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn split_keeps_unknown_nested_keys_verbatim() {
+        let raw = "---\nid: x\nlinks:\n  - one\n  - two\n---\n";
+        let (meta, _) = split(raw);
+        let meta = meta.expect("front matter");
+        assert_eq!(meta.extra, vec!["links:", "  - one", "  - two"]);
+        let rendered = render(&meta, "");
+        assert!(rendered.contains("links:\n  - one\n  - two\n"));
+    }
+}
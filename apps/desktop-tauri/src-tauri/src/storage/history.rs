@@ -0,0 +1,195 @@
+//! Keeps earlier versions of each prompt in `.history/<id>/` so an
+//! overwritten save can be looked at or brought back.
+//!
+//! Every save records the text it replaces; the log is pruned by count and
+//! age as set in the library settings.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::settings::HistorySettings;
+use super::StorageError;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionInfo {
+    pub id: String,
+    pub created_at: u64,
+    pub size: u64,
+}
+
+/// Percent-encodes an id so any id maps to exactly one file name and none can
+/// climb out of the folder it is stored in.
+pub(super) fn encode_id(prompt_id: &str) -> String {
+    let mut encoded = String::new();
+    for byte in prompt_id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn history_dir(base: &Path, prompt_id: &str) -> PathBuf {
+    base.join(".history").join(encode_id(prompt_id))
+}
+
+fn revision_path(base: &Path, prompt_id: &str, revision_id: &str) -> Result<PathBuf, StorageError> {
+    if revision_id.is_empty() || !revision_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(StorageError::invalid(format!(
+            "Invalid revision id: {}",
+            revision_id
+        )));
+    }
+    Ok(history_dir(base, prompt_id).join(format!("{revision_id}.md")))
+}
+
+fn now_unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Removes every revision of a prompt, e.g. once it has left the trash.
+pub(super) fn forget(base: &Path, prompt_id: &str) {
+    let _ = fs::remove_dir_all(history_dir(base, prompt_id));
+}
+
+/// Stores `raw` as the newest revision of a prompt and prunes the log.
+pub(super) fn record(
+    base: &Path,
+    prompt_id: &str,
+    raw: &str,
+    limits: &HistorySettings,
+) -> Result<(), StorageError> {
+    if limits.max_revisions == 0 {
+        return Ok(());
+    }
+    let dir = history_dir(base, prompt_id);
+    fs::create_dir_all(&dir).map_err(|e| StorageError::io("create history dir", e))?;
+
+    let mut stamp = now_unix_millis();
+    while dir.join(format!("{stamp}.md")).exists() {
+        stamp += 1;
+    }
+    super::atomic_write(&dir.join(format!("{stamp}.md")), raw.as_bytes())?;
+    prune(base, prompt_id, limits);
+    Ok(())
+}
+
+pub(super) fn list(base: &Path, prompt_id: &str) -> Vec<RevisionInfo> {
+    let Ok(entries) = fs::read_dir(history_dir(base, prompt_id)) else {
+        return vec![];
+    };
+    let mut revisions: Vec<(u64, RevisionInfo)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let stamp: u64 = name.strip_suffix(".md")?.parse().ok()?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some((
+                stamp,
+                RevisionInfo {
+                    id: stamp.to_string(),
+                    created_at: stamp / 1000,
+                    size,
+                },
+            ))
+        })
+        .collect();
+    revisions.sort_by_key(|(stamp, _)| std::cmp::Reverse(*stamp));
+    revisions.into_iter().map(|(_, info)| info).collect()
+}
+
+pub(super) fn read(
+    base: &Path,
+    prompt_id: &str,
+    revision_id: &str,
+) -> Result<String, StorageError> {
+    let path = revision_path(base, prompt_id, revision_id)?;
+    fs::read_to_string(&path)
+        .map_err(|e| StorageError::io(format!("read revision {}", revision_id), e))
+}
+
+fn prune(base: &Path, prompt_id: &str, limits: &HistorySettings) {
+    let dir = history_dir(base, prompt_id);
+    let cutoff = (limits.max_age_days > 0)
+        .then(|| now_unix_millis().saturating_sub(limits.max_age_days * SECS_PER_DAY * 1000));
+    for (pos, revision) in list(base, prompt_id).into_iter().enumerate() {
+        let expired = cutoff.is_some_and(|cutoff| revision.id.parse::<u64>().unwrap_or(0) < cutoff);
+        if pos >= limits.max_revisions || expired {
+            let _ = fs::remove_file(dir.join(format!("{}.md", revision.id)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn temp_base(name: &str) -> PathBuf {
+        let base = env::temp_dir().join(format!(
+            "promptbook-history-tests-{}-{}-{}",
+            name,
+            process::id(),
+            now_unix_millis()
+        ));
+        fs::create_dir_all(&base).expect("create test base");
+        base
+    }
+
+    #[test]
+    fn record_keeps_newest_revisions_within_limit() {
+        let base = temp_base("limit");
+        let limits = HistorySettings {
+            max_revisions: 2,
+            max_age_days: 0,
+        };
+        for raw in ["one", "two", "three"] {
+            record(&base, "a", raw, &limits).expect("record revision");
+        }
+
+        let revisions = list(&base, "a");
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(read(&base, "a", &revisions[0].id).expect("read"), "three");
+        assert_eq!(read(&base, "a", &revisions[1].id).expect("read"), "two");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn prune_drops_revisions_older_than_max_age() {
+        let base = temp_base("age");
+        let dir = history_dir(&base, "a");
+        fs::create_dir_all(&dir).expect("create history dir");
+        fs::write(dir.join("1000.md"), "ancient").expect("write old revision");
+
+        let limits = HistorySettings {
+            max_revisions: 10,
+            max_age_days: 1,
+        };
+        record(&base, "a", "fresh", &limits).expect("record revision");
+
+        let revisions = list(&base, "a");
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(read(&base, "a", &revisions[0].id).expect("read"), "fresh");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn history_dir_encodes_ids_and_rejects_bad_revision_ids() {
+        let base = Path::new("/lib");
+        assert_eq!(
+            history_dir(base, "../x"),
+            base.join(".history").join("%2E%2E%2Fx")
+        );
+        assert!(read(base, "a", "../../index").is_err());
+    }
+}
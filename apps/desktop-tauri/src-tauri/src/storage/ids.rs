@@ -0,0 +1,54 @@
+//! Ids for prompt files that were dropped into the library without one.
+//!
+//! They are ULIDs: 48 bits of milliseconds since the epoch followed by 80
+//! random bits, written as 26 Crockford base32 characters, so they sort by
+//! creation time and never collide in practice. The random bits come from
+//! `RandomState`, which std seeds from the OS; ids need to be unique, not
+//! unguessable.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn ulid() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let random = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(salt);
+        hasher.finish()
+    };
+    let high = random(millis) & 0xFFFF;
+    let low = random(millis ^ high);
+    let value =
+        (u128::from(millis & 0xFFFF_FFFF_FFFF) << 80) | (u128::from(high) << 64) | u128::from(low);
+    (0..26)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 0x1F) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn ulids_are_unique_and_sort_by_time() {
+        let first = ulid();
+        assert_eq!(first.len(), 26);
+        assert!(first.bytes().all(|b| ALPHABET.contains(&b)));
+        let many: HashSet<String> = (0..1000).map(|_| ulid()).collect();
+        assert_eq!(many.len(), 1000);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(ulid()[..10] > first[..10]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Keeps the line endings of prompt files as other tools wrote them, so a
+//! save from PromptBook does not show up as a whole-file change in git.
+//!
+//! The app always works with `\n`: a browser text area turns `\r\n` into
+//! `\n` as soon as the text is edited. Files that use `\r\n` throughout are
+//! converted on read and converted back on write. Files that mix both are
+//! left as they are.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct LineEndings {
+    pub crlf: bool,
+    pub final_newline: bool,
+}
+
+impl Default for LineEndings {
+    /// What PromptBook writes for new files.
+    fn default() -> Self {
+        LineEndings {
+            crlf: false,
+            final_newline: true,
+        }
+    }
+}
+
+pub(super) fn detect(text: &str) -> LineEndings {
+    let crlf = text.matches("\r\n").count();
+    LineEndings {
+        crlf: crlf > 0 && crlf == text.matches('\n').count(),
+        final_newline: text.is_empty() || text.ends_with('\n'),
+    }
+}
+
+/// `text` with the line endings the app works with.
+pub(super) fn to_lf(text: &str, endings: LineEndings) -> String {
+    if endings.crlf {
+        text.replace("\r\n", "\n")
+    } else {
+        text.to_string()
+    }
+}
+
+/// `text` with the line endings of the file it came from.
+pub(super) fn restore(text: &str, endings: LineEndings) -> String {
+    if endings.crlf {
+        text.replace("\r\n", "\n").replace('\n', "\r\n")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_files_round_trip_and_mixed_files_are_left_alone() {
+        let crlf = "---\r\nid: a\r\n---\r\nOne\r\nTwo";
+        let endings = detect(crlf);
+        assert_eq!(
+            endings,
+            LineEndings {
+                crlf: true,
+                final_newline: false,
+            }
+        );
+        let lf = to_lf(crlf, endings);
+        assert_eq!(lf, "---\nid: a\n---\nOne\nTwo");
+        assert_eq!(restore(&lf, endings), crlf);
+
+        let mixed = "One\r\nTwo\nThree\n";
+        let endings = detect(mixed);
+        assert!(!endings.crlf && endings.final_newline);
+        assert_eq!(restore(&to_lf(mixed, endings), endings), mixed);
+    }
+}
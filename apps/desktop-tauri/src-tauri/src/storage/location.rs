@@ -0,0 +1,481 @@
+//! Works out where prompt libraries live.
+//!
+//! The default library is at `PROMPTBOOK_HOME` if set, then the location
+//! saved with `set_storage_dir`, then the platform default. On Linux the
+//! default follows the XDG base directory spec, unless a library already
+//! exists in the old `~/.config/promptbook` spot. Further libraries are
+//! registered by path in the same `config.json`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{EditorCommand, StorageError};
+
+pub(super) const HOME_OVERRIDE: &str = "PROMPTBOOK_HOME";
+pub(super) const DEFAULT_LIBRARY: &str = "default";
+const APP_DIR: &str = "promptbook";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct LibraryEntry {
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AppConfig {
+    storage_dir: Option<PathBuf>,
+    libraries: Vec<LibraryEntry>,
+    active_library: Option<String>,
+    /// Editors are set up per machine, so they live here rather than in a
+    /// library's settings.
+    editors: Option<Vec<EditorCommand>>,
+}
+
+/// An absolute path from the environment; empty or relative values are
+/// ignored, as the XDG spec asks.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+fn home_dir() -> Result<PathBuf, StorageError> {
+    env_path("HOME").ok_or(StorageError::HomeMissing)
+}
+
+/// Holds `config.json`, which remembers a custom storage location.
+pub(super) fn config_dir() -> Result<PathBuf, StorageError> {
+    #[cfg(target_os = "linux")]
+    if let Some(dir) = env_path("XDG_CONFIG_HOME") {
+        return Ok(dir.join(APP_DIR));
+    }
+    Ok(home_dir()?.join(".config").join(APP_DIR))
+}
+
+fn default_dir() -> Result<PathBuf, StorageError> {
+    #[cfg(target_os = "linux")]
+    {
+        // Earlier releases always used this, whatever XDG_CONFIG_HOME says.
+        let legacy = home_dir().map(|home| home.join(".config").join(APP_DIR));
+        if let Ok(legacy) = &legacy {
+            if holds_library(legacy) {
+                return Ok(legacy.clone());
+            }
+        }
+        if let Some(dir) = env_path("XDG_DATA_HOME") {
+            return Ok(dir.join(APP_DIR));
+        }
+        if let Ok(home) = home_dir() {
+            return Ok(home.join(".local").join("share").join(APP_DIR));
+        }
+        legacy
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    config_dir()
+}
+
+/// An index or any prompt file; old libraries may have only the latter.
+#[cfg(target_os = "linux")]
+fn holds_library(dir: &Path) -> bool {
+    super::index_path(dir).is_file()
+        || super::prompt_files(dir).is_ok_and(|files| !files.is_empty())
+}
+
+fn config_path() -> Result<PathBuf, StorageError> {
+    Ok(config_dir()?.join("config.json"))
+}
+
+fn read_config() -> AppConfig {
+    config_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn write_config(config: &AppConfig) -> Result<(), StorageError> {
+    let path = config_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| StorageError::io("create config dir", e))?;
+    }
+    let json =
+        serde_json::to_vec_pretty(config).map_err(|e| StorageError::json("serialize config", e))?;
+    super::atomic_write(&path, &json)
+}
+
+pub(super) fn storage_dir() -> Result<PathBuf, StorageError> {
+    if let Some(dir) = env_path(HOME_OVERRIDE) {
+        return Ok(dir);
+    }
+    if let Some(dir) = read_config().storage_dir.filter(|dir| dir.is_absolute()) {
+        return Ok(dir);
+    }
+    default_dir()
+}
+
+/// The default library followed by every registered one.
+pub(super) fn libraries() -> Result<Vec<LibraryEntry>, StorageError> {
+    let mut all = vec![LibraryEntry {
+        id: DEFAULT_LIBRARY.to_string(),
+        name: "Default".to_string(),
+        path: storage_dir()?,
+    }];
+    all.extend(read_config().libraries);
+    Ok(all)
+}
+
+pub(super) fn active_library() -> Result<LibraryEntry, StorageError> {
+    let active = read_config().active_library;
+    let all = libraries()?;
+    Ok(all
+        .iter()
+        .find(|entry| Some(&entry.id) == active.as_ref())
+        .unwrap_or(&all[0])
+        .clone())
+}
+
+pub(super) fn register_library(name: &str, path: &Path) -> Result<LibraryEntry, StorageError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(StorageError::invalid("Library name cannot be empty."));
+    }
+    if !path.is_absolute() {
+        return Err(StorageError::invalid(format!(
+            "Library location must be an absolute path: {}",
+            path.display()
+        )));
+    }
+    let known = libraries()?;
+    keep_apart(path, &known)?;
+    fs::create_dir_all(path).map_err(|e| StorageError::io("create library dir", e))?;
+
+    let slug = super::slugify(name);
+    let mut id = slug.clone();
+    let mut n = 2;
+    while known.iter().any(|entry| entry.id == id) {
+        id = format!("{slug}-{n}");
+        n += 1;
+    }
+    let entry = LibraryEntry {
+        id,
+        name: name.to_string(),
+        path: path.to_path_buf(),
+    };
+    let mut config = read_config();
+    config.libraries.push(entry.clone());
+    write_config(&config)?;
+    Ok(entry)
+}
+
+/// Refuses a location that is, holds or sits inside one of `libraries`: a
+/// library walks every folder below it, so nested ones would rewrite each
+/// other's prompts.
+fn keep_apart(path: &Path, libraries: &[LibraryEntry]) -> Result<(), StorageError> {
+    let path = resolve(path);
+    for entry in libraries {
+        let other = resolve(&entry.path);
+        let clash = if path == other {
+            "is already registered as"
+        } else if other.starts_with(&path) {
+            "contains the library"
+        } else if path.starts_with(&other) {
+            "is inside the library"
+        } else {
+            continue;
+        };
+        return Err(StorageError::conflict(format!(
+            "{} {} {}.",
+            path.display(),
+            clash,
+            entry.name
+        )));
+    }
+    Ok(())
+}
+
+/// `path` with symlinks and `..` resolved as far as it exists; the rest is
+/// appended as given.
+fn resolve(path: &Path) -> PathBuf {
+    let mut missing = vec![];
+    let mut existing = path;
+    loop {
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+pub(super) fn switch_library(id: &str) -> Result<LibraryEntry, StorageError> {
+    let entry = libraries()?
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| StorageError::not_found(format!("Library not found: {}", id)))?;
+    let mut config = read_config();
+    config.active_library = (id != DEFAULT_LIBRARY).then(|| id.to_string());
+    write_config(&config)?;
+    Ok(entry)
+}
+
+/// Forgets a registered library; its files stay where they are.
+pub(super) fn remove_library(id: &str) -> Result<(), StorageError> {
+    if id == DEFAULT_LIBRARY {
+        return Err(StorageError::invalid(
+            "The default library cannot be removed.",
+        ));
+    }
+    let mut config = read_config();
+    let before = config.libraries.len();
+    config.libraries.retain(|entry| entry.id != id);
+    if config.libraries.len() == before {
+        return Err(StorageError::not_found(format!(
+            "Library not found: {}",
+            id
+        )));
+    }
+    if config.active_library.as_deref() == Some(id) {
+        config.active_library = None;
+    }
+    write_config(&config)
+}
+
+pub(super) fn editors() -> Option<Vec<EditorCommand>> {
+    read_config().editors
+}
+
+pub(super) fn set_editors(editors: Vec<EditorCommand>) -> Result<(), StorageError> {
+    let mut ids = HashSet::new();
+    for editor in &editors {
+        if editor.id.trim().is_empty() || editor.name.trim().is_empty() {
+            return Err(StorageError::invalid("Editors need an id and a name."));
+        }
+        if editor.command.trim().is_empty() {
+            return Err(StorageError::invalid(format!(
+                "{} has no command.",
+                editor.name
+            )));
+        }
+        if !ids.insert(editor.id.as_str()) {
+            return Err(StorageError::conflict(format!(
+                "Editor id {} is used twice.",
+                editor.id
+            )));
+        }
+    }
+    let mut config = read_config();
+    config.editors = Some(editors);
+    write_config(&config)
+}
+
+/// Saves `target` as the active library's location, moving its files there
+/// first when `move_files` is set.
+pub(super) fn set_storage_dir(
+    target: &Path,
+    move_files: bool,
+) -> Result<LibraryEntry, StorageError> {
+    let active = active_library()?;
+    let is_default = active.id == DEFAULT_LIBRARY;
+    if is_default && env_path(HOME_OVERRIDE).is_some() {
+        return Err(StorageError::conflict(format!(
+            "{} is set and overrides the storage location.",
+            HOME_OVERRIDE
+        )));
+    }
+    if !target.is_absolute() {
+        return Err(StorageError::invalid(format!(
+            "Storage location must be an absolute path: {}",
+            target.display()
+        )));
+    }
+    let others: Vec<LibraryEntry> = libraries()?
+        .into_iter()
+        .filter(|entry| entry.id != active.id)
+        .collect();
+    keep_apart(target, &others)?;
+
+    let current = &active.path;
+    if target != current {
+        if move_files && current.is_dir() {
+            if target.starts_with(current) {
+                return Err(StorageError::invalid(
+                    "Cannot move the library into one of its own folders.",
+                ));
+            }
+            move_library(current, target)?;
+        }
+        fs::create_dir_all(target).map_err(|e| StorageError::io("create storage dir", e))?;
+    }
+
+    let mut config = read_config();
+    if is_default {
+        config.storage_dir = Some(target.to_path_buf());
+    } else if let Some(entry) = config.libraries.iter_mut().find(|e| e.id == active.id) {
+        entry.path = target.to_path_buf();
+    }
+    write_config(&config)?;
+    Ok(LibraryEntry {
+        path: target.to_path_buf(),
+        ..active
+    })
+}
+
+fn move_library(from: &Path, to: &Path) -> Result<(), StorageError> {
+    if super::index_path(to).exists() || super::prompt_files(to).is_ok_and(|f| !f.is_empty()) {
+        return Err(StorageError::conflict(format!(
+            "{} already contains a prompt library.",
+            to.display()
+        )));
+    }
+    fs::create_dir_all(to).map_err(|e| StorageError::io("create storage dir", e))?;
+
+    let _lock = super::lock::acquire(from, super::lock::TIMEOUT)?;
+    let own_config = config_path().ok();
+    let mut entries = vec![];
+    for entry in
+        fs::read_dir(from).map_err(|e| StorageError::io(format!("read dir {:?}", from), e))?
+    {
+        let entry = entry.map_err(|e| StorageError::io(format!("read dir {:?}", from), e))?;
+        let source = entry.path();
+        if Some(&source) == own_config.as_ref() || entry.file_name() == super::lock::LOCK_FILE {
+            continue;
+        }
+        entries.push((source, to.join(entry.file_name())));
+    }
+    // Checked up front, so a clash never leaves the library split across
+    // two folders.
+    if let Some((_, dest)) = entries
+        .iter()
+        .find(|(_, dest)| fs::symlink_metadata(dest).is_ok())
+    {
+        return Err(StorageError::conflict(format!(
+            "{} already exists.",
+            dest.display()
+        )));
+    }
+
+    let mut moved: Vec<(&PathBuf, &PathBuf)> = vec![];
+    let mut copied: Vec<(&PathBuf, &PathBuf)> = vec![];
+    for (source, dest) in &entries {
+        // Renames fail across volumes (e.g. into a synced folder on another
+        // disk); copy instead, and delete the originals once all of the
+        // library is across.
+        if fs::rename(source, dest).is_ok() {
+            moved.push((source, dest));
+            continue;
+        }
+        if let Err(e) = copy_recursively(source, dest) {
+            let _ = remove_path(dest);
+            for (source, dest) in moved.iter().rev() {
+                let _ = fs::rename(dest, source);
+            }
+            for (_, dest) in &copied {
+                let _ = remove_path(dest);
+            }
+            return Err(e);
+        }
+        copied.push((source, dest));
+    }
+    // The library is complete at `to` by now: whatever cannot be removed here
+    // stays behind as a stray copy rather than failing a move that happened.
+    for (source, _) in copied {
+        let _ = remove_path(source);
+    }
+    Ok(())
+}
+
+/// Copies a tree as it is: symlinks are copied as links, never followed.
+fn copy_recursively(from: &Path, to: &Path) -> Result<(), StorageError> {
+    let meta =
+        fs::symlink_metadata(from).map_err(|e| StorageError::io(format!("read {:?}", from), e))?;
+    if meta.file_type().is_symlink() {
+        copy_link(from, to)
+    } else if meta.is_dir() {
+        fs::create_dir_all(to).map_err(|e| StorageError::io(format!("create {:?}", to), e))?;
+        for entry in
+            fs::read_dir(from).map_err(|e| StorageError::io(format!("read dir {:?}", from), e))?
+        {
+            let entry = entry.map_err(|e| StorageError::io(format!("read dir {:?}", from), e))?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| StorageError::io(format!("copy {:?} -> {:?}", from, to), e))
+    }
+}
+
+fn copy_link(from: &Path, to: &Path) -> Result<(), StorageError> {
+    let target =
+        fs::read_link(from).map_err(|e| StorageError::io(format!("read link {:?}", from), e))?;
+    #[cfg(unix)]
+    let linked = std::os::unix::fs::symlink(&target, to);
+    #[cfg(windows)]
+    let linked = if from.is_dir() {
+        std::os::windows::fs::symlink_dir(&target, to)
+    } else {
+        std::os::windows::fs::symlink_file(&target, to)
+    };
+    linked.map_err(|e| StorageError::io(format!("link {:?} -> {:?}", to, target), e))
+}
+
+fn remove_path(path: &Path) -> Result<(), StorageError> {
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.is_dir()) {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .map_err(|e| StorageError::io(format!("remove {:?}", path), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[cfg(unix)]
+    #[test]
+    fn copies_symlinks_as_links() {
+        let base = env::temp_dir().join(format!("promptbook-location-tests-{}", process::id()));
+        let from = base.join("from");
+        fs::create_dir_all(from.join("work")).expect("create source");
+        fs::write(from.join("work").join("a.md"), "A").expect("write prompt");
+        std::os::unix::fs::symlink("/etc", from.join("etc")).expect("link dir");
+        std::os::unix::fs::symlink("work/a.md", from.join("b.md")).expect("link file");
+
+        let to = base.join("to");
+        copy_recursively(&from, &to).expect("copy tree");
+        assert_eq!(
+            fs::read_to_string(to.join("work").join("a.md")).expect("read"),
+            "A"
+        );
+        assert_eq!(
+            fs::read_link(to.join("etc")).expect("read link"),
+            Path::new("/etc")
+        );
+        assert_eq!(
+            fs::read_link(to.join("b.md")).expect("read link"),
+            Path::new("work/a.md")
+        );
+
+        remove_path(&to.join("etc")).expect("remove link");
+        assert!(Path::new("/etc").is_dir());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
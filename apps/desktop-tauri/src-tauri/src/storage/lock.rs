@@ -0,0 +1,364 @@
+//! Advisory lock on a library folder, so the menubar panel, the main window
+//! and any other process never interleave prompt file and index writes.
+//!
+//! The lock is a `.lock` file created with `create_new`; whoever manages to
+//! create it owns the library until the guard is dropped. The holder touches
+//! the file every [`REFRESH_EVERY`], so one that has not been touched for
+//! [`STALE_AFTER`] is left over from a crash and is taken over. Nested
+//! acquisitions on the same thread (one storage call built on another) share
+//! the outer lock.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::StorageError;
+
+pub(super) const LOCK_FILE: &str = ".lock";
+pub(super) const TIMEOUT: Duration = Duration::from_secs(5);
+const STALE_AFTER: Duration = Duration::from_secs(30);
+#[cfg(not(test))]
+const REFRESH_EVERY: Duration = Duration::from_secs(10);
+#[cfg(test)]
+const REFRESH_EVERY: Duration = Duration::from_millis(20);
+const RETRY_EVERY: Duration = Duration::from_millis(10);
+
+static TOKENS: AtomicU64 = AtomicU64::new(0);
+
+struct Held {
+    depth: usize,
+    /// Written into the lock file, so a release never removes a lock that
+    /// has since been taken over by someone else.
+    contents: String,
+    /// Stops the refresh thread when dropped.
+    _refresh: mpsc::Sender<()>,
+}
+
+thread_local! {
+    static HELD: RefCell<HashMap<PathBuf, Held>> = RefCell::new(HashMap::new());
+}
+
+pub(super) struct LockGuard {
+    path: PathBuf,
+    // Depth is tracked per thread, so the guard must be dropped where it was
+    // taken.
+    _not_send: PhantomData<*const ()>,
+}
+
+pub(super) fn acquire(base: &Path, timeout: Duration) -> Result<LockGuard, StorageError> {
+    let path = base.join(LOCK_FILE);
+    let guard = LockGuard {
+        path: path.clone(),
+        _not_send: PhantomData,
+    };
+    let reentered = HELD.with(|held| {
+        held.borrow_mut()
+            .get_mut(&path)
+            .map(|held| held.depth += 1)
+            .is_some()
+    });
+    if reentered {
+        return Ok(guard);
+    }
+
+    fs::create_dir_all(base).map_err(|e| StorageError::io("create storage dir", e))?;
+    let contents = format!("{}\n{}\n", process::id(), unique_token());
+    let deadline = Instant::now() + timeout;
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                let _ = file.write_all(contents.as_bytes());
+                let held = Held {
+                    depth: 1,
+                    contents,
+                    _refresh: keep_fresh(path.clone()),
+                };
+                HELD.with(|map| map.borrow_mut().insert(path, held));
+                return Ok(guard);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if take_over_if_stale(&path) {
+                    continue;
+                }
+                if Instant::now() >= deadline {
+                    let holder = fs::read_to_string(&path).unwrap_or_default();
+                    return Err(StorageError::Locked {
+                        message: format!(
+                            "The library is busy (locked by process {}). Try again in a moment.",
+                            holder.lines().next().unwrap_or_default().trim()
+                        ),
+                    });
+                }
+                thread::sleep(RETRY_EVERY);
+            }
+            Err(e) => return Err(StorageError::io("create lock file", e)),
+        }
+    }
+}
+
+fn unique_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!(
+        "{}-{}-{}",
+        process::id(),
+        nanos,
+        TOKENS.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Touches the lock file until the returned sender is dropped, so a long
+/// hold is never mistaken for a crashed one.
+fn keep_fresh(path: PathBuf) -> mpsc::Sender<()> {
+    let (tx, rx) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        while let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(REFRESH_EVERY) {
+            if let Ok(file) = OpenOptions::new().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+        }
+    });
+    tx
+}
+
+/// Moves a stale lock out of the way and reports whether it did. The rename
+/// is atomic, so of several processes that found the same stale lock only
+/// one takes it over; the others go back to waiting on whoever creates the
+/// next one.
+fn take_over_if_stale(path: &Path) -> bool {
+    if !is_stale(path) {
+        return false;
+    }
+    let Ok(stale) = fs::read(path) else {
+        return false;
+    };
+    let aside = path.with_file_name(format!("{}.stale-{}", LOCK_FILE, unique_token()));
+    if fs::rename(path, &aside).is_err() {
+        return false;
+    }
+    if fs::read(&aside).ok() == Some(stale) {
+        let _ = fs::remove_file(&aside);
+        return true;
+    }
+    // The lock changed hands between the check and the rename: put the live
+    // one back. Should a new lock be in its place already, the live one stays
+    // aside rather than be lost.
+    if fs::hard_link(&aside, path).is_ok() {
+        let _ = fs::remove_file(&aside);
+    }
+    false
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let released = HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            let entry = held.get_mut(&self.path)?;
+            entry.depth -= 1;
+            if entry.depth > 0 {
+                return None;
+            }
+            held.remove(&self.path)
+        });
+        if let Some(released) = released {
+            if fs::read_to_string(&self.path).ok() == Some(released.contents) {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_base(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let base = env::temp_dir().join(format!(
+            "promptbook-lock-tests-{}-{}-{}",
+            name,
+            process::id(),
+            unique
+        ));
+        fs::create_dir_all(&base).expect("create test base");
+        base
+    }
+
+    #[test]
+    fn nested_acquire_shares_the_lock_and_last_drop_releases_it() {
+        let base = temp_base("nested");
+        let outer = acquire(&base, TIMEOUT).expect("outer lock");
+        let inner = acquire(&base, TIMEOUT).expect("inner lock");
+        drop(inner);
+        assert!(base.join(LOCK_FILE).exists());
+        drop(outer);
+        assert!(!base.join(LOCK_FILE).exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn times_out_while_another_holder_keeps_the_lock() {
+        let base = temp_base("timeout");
+        fs::write(base.join(LOCK_FILE), "4242\n").expect("write foreign lock");
+
+        let err = acquire(&base, Duration::from_millis(50))
+            .err()
+            .expect("expected timeout");
+        assert!(matches!(err, StorageError::Locked { .. }));
+        assert!(err.to_string().contains("4242"));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn takes_over_stale_locks() {
+        let base = temp_base("stale");
+        let file = fs::File::create(base.join(LOCK_FILE)).expect("write stale lock");
+        let long_ago = SystemTime::now() - STALE_AFTER - Duration::from_secs(1);
+        file.set_modified(long_ago).expect("age lock file");
+
+        let guard = acquire(&base, Duration::from_millis(50)).expect("take over stale lock");
+        drop(guard);
+        assert!(!base.join(LOCK_FILE).exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn a_held_lock_stays_fresh_and_is_only_released_by_its_holder() {
+        let base = temp_base("fresh");
+        let path = base.join(LOCK_FILE);
+        let guard = acquire(&base, TIMEOUT).expect("lock");
+        let long_ago = SystemTime::now() - STALE_AFTER - Duration::from_secs(1);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .expect("open lock")
+            .set_modified(long_ago)
+            .expect("age lock file");
+        thread::sleep(REFRESH_EVERY * 5);
+        assert!(!is_stale(&path));
+
+        fs::write(&path, "4242\nsomeone-else\n").expect("replace lock");
+        drop(guard);
+        assert!(path.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn only_one_taker_wins_a_stale_lock() {
+        let base = temp_base("takeover");
+        let path = base.join(LOCK_FILE);
+        let file = fs::File::create(&path).expect("write stale lock");
+        file.set_modified(SystemTime::now() - STALE_AFTER - Duration::from_secs(1))
+            .expect("age lock file");
+
+        assert!(take_over_if_stale(&path));
+        assert!(!take_over_if_stale(&path));
+        let leftovers: Vec<_> = fs::read_dir(&base).expect("read base").collect();
+        assert!(leftovers.is_empty());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    /// Set for the copies of the test binary that
+    /// `processes_take_turns_and_one_takes_over_a_stale_lock` starts.
+    const CHILD_BASE: &str = "PROMPTBOOK_LOCK_TEST_BASE";
+
+    fn bump_counter(base: &Path) {
+        let _guard = acquire(base, TIMEOUT).expect("lock");
+        let counter = base.join("counter");
+        let n: u32 = fs::read_to_string(&counter)
+            .ok()
+            .and_then(|raw| raw.parse().ok())
+            .unwrap_or(0);
+        thread::sleep(Duration::from_millis(1));
+        fs::write(&counter, (n + 1).to_string()).expect("write counter");
+    }
+
+    /// Does nothing unless started by the test below.
+    #[test]
+    fn child_process_bumps_the_counter() {
+        let Some(base) = env::var_os(CHILD_BASE) else {
+            return;
+        };
+        for _ in 0..10 {
+            bump_counter(Path::new(&base));
+        }
+    }
+
+    #[test]
+    fn processes_take_turns_and_one_takes_over_a_stale_lock() {
+        let base = temp_base("processes");
+        let file = fs::File::create(base.join(LOCK_FILE)).expect("write stale lock");
+        file.set_modified(SystemTime::now() - STALE_AFTER - Duration::from_secs(1))
+            .expect("age lock file");
+
+        let module = module_path!().split_once("::").map_or("", |(_, rest)| rest);
+        let children: Vec<_> = (0..4)
+            .map(|_| {
+                process::Command::new(env::current_exe().expect("test binary"))
+                    .args(["--exact", "--test-threads=1"])
+                    .arg(format!("{module}::child_process_bumps_the_counter"))
+                    .env(CHILD_BASE, &base)
+                    .stdout(process::Stdio::null())
+                    .spawn()
+                    .expect("start child process")
+            })
+            .collect();
+        for mut child in children {
+            assert!(child.wait().expect("wait for child").success());
+        }
+        let total = fs::read_to_string(base.join("counter")).expect("read counter");
+        assert_eq!(total, "40");
+        assert!(!base.join(LOCK_FILE).exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn threads_take_turns() {
+        let base = temp_base("threads");
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let base = base.clone();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        let _guard = acquire(&base, TIMEOUT).expect("lock");
+                        let counter = base.join("counter");
+                        let n: u32 = fs::read_to_string(&counter)
+                            .ok()
+                            .and_then(|raw| raw.parse().ok())
+                            .unwrap_or(0);
+                        thread::sleep(Duration::from_millis(1));
+                        fs::write(&counter, (n + 1).to_string()).expect("write counter");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("writer thread");
+        }
+        let total = fs::read_to_string(base.join("counter")).expect("read counter");
+        assert_eq!(total, "40");
+        let _ = fs::remove_dir_all(&base);
+    }
+}
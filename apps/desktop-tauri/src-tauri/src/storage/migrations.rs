@@ -0,0 +1,174 @@
+//! Upgrades older `index.json` shapes one version at a time.
+//!
+//! Every step takes the raw JSON of version `n` and returns version `n + 1`,
+//! so an index from any earlier release walks the whole chain.
+
+use serde_json::{json, Value};
+
+pub(super) const INDEX_VERSION: u32 = 2;
+
+type Step = fn(Value) -> Result<Value, String>;
+
+/// Steps keyed by the version they upgrade from.
+const STEPS: &[(u32, Step)] = &[(1, v1_to_v2)];
+
+/// The version an index declares. One without a `version` predates
+/// versioning and is v1; anything but a whole number from 1 up was never
+/// written by a release.
+fn version_of(index: &Value) -> Result<u32, String> {
+    match index.get("version") {
+        None | Some(Value::Null) => Ok(1),
+        Some(raw) => raw
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("index.json has an invalid format version ({raw}).")),
+    }
+}
+
+/// The version of an index this release can read, migrating it if needed.
+/// Anything else (newer, or without a way up) must be left as it is.
+pub(super) fn supported_version(index: &Value) -> Result<u32, String> {
+    let version = version_of(index)?;
+    if version > INDEX_VERSION {
+        return Err(format!(
+            "index.json uses format version {version}, which this version of PromptBook cannot update."
+        ));
+    }
+    if let Some(missing) = missing_step(version, STEPS) {
+        return Err(format!(
+            "index.json uses format version {version}, which cannot be migrated: there is no step from version {missing}."
+        ));
+    }
+    Ok(version)
+}
+
+fn missing_step(version: u32, steps: &[(u32, Step)]) -> Option<u32> {
+    (version..INDEX_VERSION).find(|v| !steps.iter().any(|(from, _)| from == v))
+}
+
+pub(super) fn migrate(mut index: Value) -> Result<Value, String> {
+    let mut version = supported_version(&index)?;
+    while version < INDEX_VERSION {
+        let Some((_, step)) = STEPS.iter().find(|(from, _)| *from == version) else {
+            return Err(format!("No migration from index version {}", version));
+        };
+        index = step(index)?;
+        version += 1;
+        index["version"] = json!(version);
+    }
+    Ok(index)
+}
+
+/// v2 adds folders, tags and creation times to every entry.
+fn v1_to_v2(mut index: Value) -> Result<Value, String> {
+    let prompts = index
+        .get_mut("prompts")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "index has no prompts list".to_string())?;
+    for prompt in prompts {
+        let Some(entry) = prompt.as_object_mut() else {
+            continue;
+        };
+        let file = entry.get("file").and_then(Value::as_str).unwrap_or("");
+        let folder = file.rsplit_once('/').map(|(f, _)| f).unwrap_or("");
+        let folder = json!(folder);
+        let updated_at = entry.get("updatedAt").cloned().unwrap_or(json!(0));
+        entry.entry("folder").or_insert(folder);
+        entry.entry("tags").or_insert(json!([]));
+        entry.entry("createdAt").or_insert(updated_at);
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_FIXTURE: &str = r#"{
+        "version": 1,
+        "prompts": [
+            {
+                "id": "a",
+                "file": "bug-triage.md",
+                "title": "Bug Triage",
+                "copied": 3,
+                "searched": 1,
+                "updatedAt": 1700000000
+            }
+        ]
+    }"#;
+
+    const V2_FIXTURE: &str = r#"{
+        "version": 2,
+        "prompts": [
+            {
+                "id": "a",
+                "file": "review/bug-triage.md",
+                "folder": "review",
+                "title": "Bug Triage",
+                "copied": 3,
+                "searched": 1,
+                "tags": ["review"],
+                "createdAt": 1600000000,
+                "updatedAt": 1700000000
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn migrates_v1_fixture_to_current_version() {
+        let v1: Value = serde_json::from_str(V1_FIXTURE).expect("parse fixture");
+        let migrated = migrate(v1).expect("migrate");
+
+        assert_eq!(version_of(&migrated), Ok(INDEX_VERSION));
+        let entry = &migrated["prompts"][0];
+        assert_eq!(entry["folder"], json!(""));
+        assert_eq!(entry["tags"], json!([]));
+        assert_eq!(entry["createdAt"], json!(1700000000));
+        assert_eq!(entry["copied"], json!(3));
+    }
+
+    #[test]
+    fn leaves_v2_fixture_untouched() {
+        let v2: Value = serde_json::from_str(V2_FIXTURE).expect("parse fixture");
+        assert_eq!(migrate(v2.clone()).expect("migrate"), v2);
+    }
+
+    #[test]
+    fn treats_missing_version_as_v1() {
+        let unversioned = json!({ "prompts": [{ "id": "a", "file": "x/y.md" }] });
+        let migrated = migrate(unversioned).expect("migrate");
+        assert_eq!(migrated["version"], json!(INDEX_VERSION));
+        assert_eq!(migrated["prompts"][0]["folder"], json!("x"));
+    }
+
+    #[test]
+    fn rejects_versions_no_release_wrote() {
+        assert_eq!(version_of(&json!({ "version": null })), Ok(1));
+        for raw in [
+            json!(0),
+            json!(-1),
+            json!(1.5),
+            json!("2"),
+            json!(u64::from(u32::MAX) + 3),
+        ] {
+            let index = json!({ "version": raw, "prompts": [] });
+            assert!(version_of(&index).is_err(), "{raw}");
+            assert!(migrate(index).is_err(), "{raw}");
+        }
+        assert!(supported_version(&json!({ "version": INDEX_VERSION + 1 })).is_err());
+    }
+
+    #[test]
+    fn finds_gaps_in_the_migration_chain() {
+        assert_eq!(missing_step(1, STEPS), None);
+        assert_eq!(missing_step(INDEX_VERSION, &[]), None);
+        assert_eq!(missing_step(1, &[]), Some(1));
+    }
+
+    #[test]
+    fn rejects_v1_without_prompts_list() {
+        assert!(migrate(json!({ "version": 1 })).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Checks library-relative file names that come from outside the code that
+//! created them: `index.json` (which may be synced, hand-edited or crafted)
+//! and the UI.
+//!
+//! A name is only joined onto the library root if it stays inside it: no
+//! `..`, no absolute or drive paths, no dot-entries (our own bookkeeping) and
+//! no symlinks along the way, which `walk_storage` never follows either.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether `file` names a regular `.md` file inside `base`. A file that does
+/// not exist yet passes as long as the path to it stays inside.
+pub(super) fn is_prompt_file(base: &Path, file: &str) -> bool {
+    file.ends_with(".md")
+        && resolve(base, file).is_some_and(|(path, exists)| !exists || path.is_file())
+}
+
+/// Like [`is_prompt_file`], for a folder.
+pub(super) fn is_folder(base: &Path, folder: &str) -> bool {
+    resolve(base, folder).is_some_and(|(path, exists)| !exists || path.is_dir())
+}
+
+/// The path `rel` leads to and whether it exists, or `None` if getting there
+/// would leave `base`.
+fn resolve(base: &Path, rel: &str) -> Option<(PathBuf, bool)> {
+    if rel.contains('\\') {
+        return None;
+    }
+    let parts: Vec<&str> = rel.split('/').collect();
+    if parts
+        .iter()
+        .any(|part| part.is_empty() || part.starts_with('.') || part.contains(':'))
+    {
+        return None;
+    }
+
+    let mut path = base.to_path_buf();
+    for part in parts {
+        path.push(part);
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => return None,
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Some((path, false)),
+            Err(_) => return None,
+        }
+    }
+    Some((path, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn rejects_names_that_leave_the_library() {
+        let base = env::temp_dir().join(format!("promptbook-paths-tests-{}", process::id()));
+        fs::create_dir_all(base.join("review")).expect("create test root");
+        fs::write(base.join("review").join("a.md"), "A").expect("write prompt");
+
+        assert!(is_prompt_file(&base, "review/a.md"));
+        assert!(is_prompt_file(&base, "not-yet-written.md"));
+        for hostile in [
+            "../outside.md",
+            "review/../../outside.md",
+            "/etc/passwd.md",
+            "C:/Windows/win.md",
+            "..\\outside.md",
+            "review//a.md",
+            "./review/a.md",
+            ".history/a.md",
+            "review/a.txt",
+            "review",
+            "",
+        ] {
+            assert!(!is_prompt_file(&base, hostile), "{hostile}");
+        }
+
+        fs::create_dir_all(base.join("folder.md")).expect("create folder");
+        assert!(!is_prompt_file(&base, "folder.md"));
+        assert!(is_folder(&base, "review"));
+        assert!(is_folder(&base, "gone"));
+        assert!(!is_folder(&base, "review/a.md"));
+        assert!(!is_folder(&base, "../review"));
+        let _ = fs::remove_dir_all(&base);
+    }
+}
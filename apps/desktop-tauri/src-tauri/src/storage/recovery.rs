@@ -0,0 +1,157 @@
+//! Rebuilds what it can from an `index.json` that no longer parses.
+//!
+//! The broken file is moved aside as `index.json.corrupt-<ts>` so nothing is
+//! lost, every entry that still parses on its own is kept, and anything else
+//! is filled in from `index.json.bak`, the last index that was known good.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{PromptIndex, PromptIndexEntry};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexRecovery {
+    /// File name the corrupt index was moved to, if moving it succeeded.
+    pub quarantined: Option<String>,
+    /// Entries recovered from the corrupt file itself.
+    pub salvaged: usize,
+    /// Entries recovered from the last good backup.
+    pub from_backup: usize,
+}
+
+/// Recoveries not reported yet, by library folder.
+static PENDING: Mutex<BTreeMap<PathBuf, Vec<IndexRecovery>>> = Mutex::new(BTreeMap::new());
+
+pub(super) fn backup_path(base: &Path) -> PathBuf {
+    base.join("index.json.bak")
+}
+
+/// Keeps a copy of the index that is about to be replaced, as long as it is
+/// still readable.
+pub(super) fn back_up(base: &Path, current: &[u8]) {
+    if serde_json::from_slice::<PromptIndex>(current).is_ok() {
+        let _ = fs::write(backup_path(base), current);
+    }
+}
+
+pub(super) fn recover(base: &Path, raw: &str) -> Vec<PromptIndexEntry> {
+    let quarantined = quarantine(base);
+
+    let mut entries = salvage(raw);
+    let salvaged = entries.len();
+    let backup = fs::read_to_string(backup_path(base))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<PromptIndex>(&raw).ok())
+        .map(|index| index.prompts)
+        .unwrap_or_default();
+    for entry in backup {
+        if !entries
+            .iter()
+            .any(|e| e.file == entry.file || e.id == entry.id)
+        {
+            entries.push(entry);
+        }
+    }
+
+    let report = IndexRecovery {
+        quarantined,
+        salvaged,
+        from_backup: entries.len() - salvaged,
+    };
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.entry(base.to_path_buf()).or_default().push(report);
+    }
+    entries
+}
+
+/// Recoveries of the library at `base` since the last call, oldest first.
+pub(super) fn take_pending(base: &Path) -> Vec<IndexRecovery> {
+    PENDING
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(base))
+        .unwrap_or_default()
+}
+
+fn quarantine(base: &Path) -> Option<String> {
+    let mut stamp = super::now_unix_secs();
+    while base.join(format!("index.json.corrupt-{stamp}")).exists() {
+        stamp += 1;
+    }
+    let name = format!("index.json.corrupt-{stamp}");
+    fs::rename(super::index_path(base), base.join(&name))
+        .ok()
+        .map(|_| name)
+}
+
+/// Parses each object inside the top-level `prompts` array on its own, so a
+/// single bad entry or a truncated tail only costs the entries it touches.
+fn salvage(raw: &str) -> Vec<PromptIndexEntry> {
+    let mut entries = vec![];
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = None;
+
+    for (pos, ch) in raw.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' | '[' => {
+                if ch == '{' && depth == 2 {
+                    start = Some(pos);
+                }
+                depth += 1;
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if ch == '}' && depth == 2 {
+                    if let Some(from) = start.take() {
+                        if let Ok(entry) = serde_json::from_str(&raw[from..=pos]) {
+                            entries.push(entry);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salvage_keeps_valid_entries_around_broken_ones() {
+        let raw = r#"{
+            "version": 2,
+            "prompts": [
+                { "id": "a", "file": "a.md", "title": "A {x}", "copied": 1, "searched": 0, "tags": ["t"], "updatedAt": 5 },
+                { "id": "b", "file": "b.md", "title": 42 },
+                { "id": "c", "file": "c.md", "title": "C \"quoted\"", "copied": 2, "searched": 0, "updatedAt": 6 },
+                { "id": "d", "file": "d.md", "title": "trunc"#;
+
+        let ids: Vec<String> = salvage(raw).into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn salvage_returns_nothing_for_garbage() {
+        assert!(salvage("not json").is_empty());
+        assert!(salvage("").is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Per-library settings, stored in `settings.json` at the library root so
+//! they travel with the prompts.
+//!
+//! A missing or unreadable file, and any field it leaves out, falls back to
+//! the defaults below.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::StorageError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistorySettings {
+    /// Revisions kept per prompt; `0` disables history.
+    pub max_revisions: usize,
+    /// Revisions older than this are pruned; `0` keeps them forever.
+    pub max_age_days: u64,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            max_revisions: 50,
+            max_age_days: 90,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrashSettings {
+    /// Deleted prompts are purged after this many days; `0` keeps them forever.
+    pub retention_days: u64,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TempFileSettings {
+    /// Leftover temp files older than this are removed at startup; `0` keeps
+    /// them.
+    pub max_age_hours: u64,
+}
+
+impl Default for TempFileSettings {
+    fn default() -> Self {
+        Self { max_age_hours: 24 }
+    }
+}
+
+/// A save that would remove at least `min_files` prompt files and more than
+/// `max_percent` of the library is refused unless forced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MassDeleteSettings {
+    pub min_files: usize,
+    pub max_percent: usize,
+}
+
+impl Default for MassDeleteSettings {
+    fn default() -> Self {
+        Self {
+            min_files: 2,
+            max_percent: 50,
+        }
+    }
+}
+
+impl MassDeleteSettings {
+    pub(super) fn blocks(&self, existing: usize, removing: usize) -> bool {
+        removing >= self.min_files.max(1) && removing * 100 > existing * self.max_percent
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LibrarySettings {
+    pub history: HistorySettings,
+    pub trash: TrashSettings,
+    pub mass_delete: MassDeleteSettings,
+    pub temp_files: TempFileSettings,
+}
+
+fn settings_path(base: &Path) -> PathBuf {
+    base.join("settings.json")
+}
+
+pub(super) fn read(base: &Path) -> LibrarySettings {
+    fs::read_to_string(settings_path(base))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub(super) fn write(base: &Path, settings: &LibrarySettings) -> Result<(), StorageError> {
+    let json = serde_json::to_vec_pretty(settings)
+        .map_err(|e| StorageError::json("serialize settings", e))?;
+    super::atomic_write(&settings_path(base), &json)
+}
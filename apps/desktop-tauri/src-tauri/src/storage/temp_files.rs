@@ -0,0 +1,154 @@
+//! Removes temp files nobody is going to come back for: copies earlier
+//! releases handed to external editors in `.tmp/`, the `*.tmp` files an
+//! `atomic_write` left behind when the app died before renaming them, and
+//! stale locks a take-over could not put back.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const EDITOR_TEMP_DIR: &str = ".tmp";
+
+/// What `atomic_write` leaves behind at the library root: the index and the
+/// library settings.
+const ROOT_LEFTOVERS: &[&str] = &["index.json.tmp", "settings.json.tmp"];
+
+/// Prefix of the names a stale lock is moved aside to while it is taken over.
+const STALE_LOCK_PREFIX: &str = ".lock.stale-";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Place {
+    Root,
+    /// A folder the user made.
+    Folder,
+    /// One of our own dot-folders (`.history`, `.trash`, `.conflicts`, ...).
+    Bookkeeping,
+    EditorTemp,
+}
+
+/// Deletes temp files under `base` last modified more than `max_age` ago and
+/// returns how many went. Outside `.tmp/` only the exact names
+/// `atomic_write` uses are touched, so a user's own `draft.tmp` stays.
+pub(super) fn sweep(base: &Path, max_age: Duration) -> usize {
+    let Some(cutoff) = SystemTime::now().checked_sub(max_age) else {
+        return 0;
+    };
+    let removed = sweep_dir(base, cutoff, Place::Root);
+    // Only drops the folder once it is empty.
+    let _ = fs::remove_dir(base.join(EDITOR_TEMP_DIR));
+    removed
+}
+
+fn is_leftover(name: &str, place: Place) -> bool {
+    match place {
+        Place::EditorTemp => true,
+        Place::Root => {
+            name.ends_with(".md.tmp")
+                || ROOT_LEFTOVERS.contains(&name)
+                || name.starts_with(STALE_LOCK_PREFIX)
+        }
+        Place::Folder => name.ends_with(".md.tmp"),
+        Place::Bookkeeping => name.ends_with(".md.tmp") || name.ends_with(".json.tmp"),
+    }
+}
+
+fn sweep_dir(dir: &Path, cutoff: SystemTime, place: Place) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            let inner = match place {
+                Place::EditorTemp | Place::Bookkeeping => place,
+                Place::Root if name == EDITOR_TEMP_DIR => Place::EditorTemp,
+                _ if name.starts_with('.') => Place::Bookkeeping,
+                _ => Place::Folder,
+            };
+            removed += sweep_dir(&path, cutoff, inner);
+            continue;
+        }
+        let is_old = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if file_type.is_file()
+            && is_leftover(&name, place)
+            && is_old
+            && fs::remove_file(&path).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+    use std::time::UNIX_EPOCH;
+
+    fn temp_base() -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let base = env::temp_dir().join(format!(
+            "promptbook-temp-files-tests-{}-{}",
+            process::id(),
+            unique
+        ));
+        fs::create_dir_all(base.join(EDITOR_TEMP_DIR)).expect("create test base");
+        fs::create_dir_all(base.join("review")).expect("create folder");
+        base
+    }
+
+    fn write_aged(path: &Path, age: Duration) {
+        let file = fs::File::create(path).expect("create file");
+        file.set_modified(SystemTime::now() - age)
+            .expect("set modified time");
+    }
+
+    #[test]
+    fn sweep_removes_only_old_temp_files() {
+        let base = temp_base();
+        let day = Duration::from_secs(24 * 60 * 60);
+        write_aged(&base.join(EDITOR_TEMP_DIR).join("old-copy.md"), day * 3);
+        write_aged(
+            &base.join(EDITOR_TEMP_DIR).join("new-copy.md"),
+            Duration::from_secs(1),
+        );
+        write_aged(&base.join("review").join("a.md.tmp"), day * 3);
+        write_aged(&base.join("index.json.tmp"), Duration::from_secs(1));
+        write_aged(&base.join("review").join("a.md"), day * 3);
+        write_aged(&base.join("review").join("draft.tmp"), day * 3);
+        write_aged(&base.join("notes.json.tmp"), day * 3);
+        write_aged(&base.join(".lock.stale-1-2-3"), day * 3);
+        write_aged(&base.join(".lock"), day * 3);
+
+        assert_eq!(sweep(&base, day), 3);
+        assert!(!base.join(".lock.stale-1-2-3").exists());
+        assert!(base.join(".lock").exists());
+        assert!(!base.join(EDITOR_TEMP_DIR).join("old-copy.md").exists());
+        assert!(base.join(EDITOR_TEMP_DIR).join("new-copy.md").exists());
+        assert!(!base.join("review").join("a.md.tmp").exists());
+        assert!(base.join("index.json.tmp").exists());
+        assert!(base.join("review").join("a.md").exists());
+
+        assert_eq!(sweep(&base, Duration::ZERO), 2);
+        assert!(!base.join(EDITOR_TEMP_DIR).exists());
+        assert!(base.join("review").join("draft.tmp").exists());
+        assert!(base.join("notes.json.tmp").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,168 @@
+//! Deleted prompts are not removed straight away: they move to
+//! `.trash/<id>/` with a small metadata record so they can be restored.
+//!
+//! Items older than the library's retention are purged; once the last trashed
+//! copy of a prompt is gone, so is its revision history.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::StorageError;
+
+const PROMPT_FILE: &str = "prompt.md";
+const META_FILE: &str = "meta.json";
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub prompt_id: String,
+    pub title: String,
+    pub file: String,
+    pub deleted_at: u64,
+}
+
+fn trash_dir(base: &Path) -> PathBuf {
+    base.join(".trash")
+}
+
+fn item_dir(base: &Path, trash_id: &str) -> Result<PathBuf, StorageError> {
+    if trash_id.is_empty() || !trash_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(StorageError::invalid(format!(
+            "Invalid trash id: {}",
+            trash_id
+        )));
+    }
+    Ok(trash_dir(base).join(trash_id))
+}
+
+/// Moves a prompt file into `.trash/<id>/` next to a small metadata record.
+pub(super) fn move_to_trash(
+    base: &Path,
+    file: &str,
+    prompt_id: &str,
+    title: &str,
+) -> Result<TrashEntry, StorageError> {
+    let now = super::now_unix_secs();
+    let mut stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    while trash_dir(base).join(stamp.to_string()).exists() {
+        stamp += 1;
+    }
+
+    let entry = TrashEntry {
+        id: stamp.to_string(),
+        prompt_id: prompt_id.to_string(),
+        title: title.to_string(),
+        file: file.to_string(),
+        deleted_at: now,
+    };
+    let dir = trash_dir(base).join(&entry.id);
+    fs::create_dir_all(&dir).map_err(|e| StorageError::io("create trash dir", e))?;
+    let json =
+        serde_json::to_vec_pretty(&entry).map_err(|e| StorageError::json("serialize trash", e))?;
+    let moved = super::atomic_write(&dir.join(META_FILE), &json).and_then(|_| {
+        fs::rename(base.join(file), dir.join(PROMPT_FILE))
+            .map_err(|e| StorageError::io(format!("move {} to trash", file), e))
+    });
+    if let Err(error) = moved {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(error);
+    }
+    Ok(entry)
+}
+
+pub(super) fn list(base: &Path) -> Vec<TrashEntry> {
+    let Ok(entries) = fs::read_dir(trash_dir(base)) else {
+        return vec![];
+    };
+    let mut items: Vec<TrashEntry> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_to_string(entry.path().join(META_FILE)).ok())
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.id.parse::<u64>().unwrap_or(0)));
+    items
+}
+
+/// Hands back the trashed file path and its metadata; the caller decides where
+/// the file goes and calls [`forget`] once it has been moved out.
+pub(super) fn take(base: &Path, trash_id: &str) -> Result<(PathBuf, TrashEntry), StorageError> {
+    let dir = item_dir(base, trash_id)?;
+    let raw = fs::read_to_string(dir.join(META_FILE))
+        .map_err(|_| StorageError::not_found(format!("Trash item not found: {}", trash_id)))?;
+    let entry: TrashEntry =
+        serde_json::from_str(&raw).map_err(|e| StorageError::json("read trash item", e))?;
+    Ok((dir.join(PROMPT_FILE), entry))
+}
+
+pub(super) fn forget(base: &Path, trash_id: &str) -> Result<(), StorageError> {
+    let dir = item_dir(base, trash_id)?;
+    fs::remove_dir_all(&dir).map_err(|e| StorageError::io("remove trash item", e))
+}
+
+pub(super) fn empty(base: &Path) -> Result<usize, StorageError> {
+    let items = list(base);
+    let dir = trash_dir(base);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| StorageError::io("empty trash", e))?;
+    }
+    forget_history(base, &items);
+    Ok(items.len())
+}
+
+/// Deletes trash items older than `retention_days`; `0` keeps them forever.
+pub(super) fn purge(base: &Path, retention_days: u64) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let cutoff = super::now_unix_secs().saturating_sub(retention_days * SECS_PER_DAY);
+    let purged: Vec<TrashEntry> = list(base)
+        .into_iter()
+        .filter(|item| item.deleted_at < cutoff)
+        .filter(|item| forget(base, &item.id).is_ok())
+        .collect();
+    forget_history(base, &purged);
+    purged.len()
+}
+
+/// Drops the revision history of purged prompts that are neither back in the
+/// index nor still in the trash under another item.
+fn forget_history(base: &Path, purged: &[TrashEntry]) {
+    let index = super::read_index(base);
+    let remaining = list(base);
+    for item in purged {
+        let in_use = index.prompts.iter().any(|entry| entry.id == item.prompt_id)
+            || remaining
+                .iter()
+                .any(|other| other.prompt_id == item.prompt_id);
+        if !in_use {
+            super::history::forget(base, &item.prompt_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn a_failed_move_leaves_no_item_behind() {
+        let base = env::temp_dir().join(format!("promptbook-trash-tests-{}", process::id()));
+        fs::create_dir_all(&base).expect("create test base");
+
+        assert!(move_to_trash(&base, "missing.md", "a", "Missing").is_err());
+        assert!(list(&base).is_empty());
+        assert_eq!(
+            fs::read_dir(trash_dir(&base)).expect("read trash").count(),
+            0
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+}
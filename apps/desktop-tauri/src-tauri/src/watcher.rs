@@ -0,0 +1,210 @@
+//! Watches a library folder for prompt files and folders changed by other
+//! programs: an editor, `git pull`, a sync client bringing in edits from
+//! another machine.
+//!
+//! Raw events arrive in bursts (an editor's save is often a write, a rename
+//! and a metadata change), so they are collected until the folder has been
+//! quiet for [`DEBOUNCE`] and reported as one list of paths. A folder renamed
+//! or removed as a whole only produces events for the folder itself, so those
+//! are reported too.
+
+use notify::event::{CreateKind, EventKind, ModifyKind, RemoveKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Reports even while events keep coming, e.g. during a long sync.
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Stops watching when dropped.
+pub struct LibraryWatcher {
+    _inner: RecommendedWatcher,
+}
+
+/// Calls `on_change` with the library-relative paths of prompt files and
+/// folders that were created, changed or removed under `root`.
+pub fn watch(
+    root: &Path,
+    on_change: impl Fn(Vec<String>) + Send + 'static,
+) -> notify::Result<LibraryWatcher> {
+    fs::create_dir_all(root)?;
+    // Some backends (FSEvents) report resolved paths.
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut inner = notify::recommended_watcher(tx)?;
+    inner.watch(&root, RecursiveMode::Recursive)?;
+
+    thread::spawn(move || {
+        // Ends once the watcher, and with it the sender, is dropped.
+        while let Ok(first) = rx.recv() {
+            let mut files = BTreeSet::new();
+            collect(&root, first, &mut files);
+            let deadline = Instant::now() + MAX_DELAY;
+            while let Ok(next) = rx.recv_timeout(DEBOUNCE) {
+                collect(&root, next, &mut files);
+                if Instant::now() >= deadline {
+                    break;
+                }
+            }
+            if !files.is_empty() {
+                on_change(files.into_iter().collect());
+            }
+        }
+    });
+
+    Ok(LibraryWatcher { _inner: inner })
+}
+
+fn collect(root: &Path, event: notify::Result<notify::Event>, files: &mut BTreeSet<String>) {
+    let Ok(event) = event else { return };
+    // Reads, including our own loads, are not changes.
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    files.extend(
+        event
+            .paths
+            .iter()
+            .filter_map(|path| library_path(root, path, &event.kind)),
+    );
+}
+
+/// The library-relative path of a prompt file or folder, or `None` for what
+/// the app keeps for itself: dot-entries (`.tmp`, `.history`, `.lock`, ...),
+/// the index, and the `*.md.tmp` files `atomic_write` renames into place.
+fn library_path(root: &Path, path: &Path, kind: &EventKind) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let mut parts = vec![];
+    for part in rel.components() {
+        let part = part.as_os_str().to_str()?;
+        if part.starts_with('.') {
+            return None;
+        }
+        parts.push(part);
+    }
+    let rel = parts.join("/");
+    if rel.is_empty() {
+        return None;
+    }
+    let folder = match kind {
+        EventKind::Create(CreateKind::Folder) | EventKind::Remove(RemoveKind::Folder) => true,
+        // Most backends do not say what was renamed or removed, and by now it
+        // may be gone: take an existing directory or a name without an
+        // extension for a folder.
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+            path.is_dir()
+                || (!path.exists() && parts.last().is_some_and(|name| !name.contains('.')))
+        }
+        _ => false,
+    };
+    (folder || rel.ends_with(".md")).then_some(rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+    use std::time::SystemTime;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let root = env::temp_dir().join(format!(
+            "promptbook-watcher-tests-{}-{}-{}",
+            name,
+            process::id(),
+            unique
+        ));
+        fs::create_dir_all(&root).expect("create test root");
+        fs::canonicalize(&root).expect("canonicalize test root")
+    }
+
+    #[test]
+    fn library_path_skips_bookkeeping() {
+        let root = Path::new("/lib");
+        let any = EventKind::Modify(ModifyKind::Any);
+        let file = |rel: &str| library_path(root, &root.join(rel), &any);
+        assert_eq!(file("a.md"), Some("a.md".to_string()));
+        assert_eq!(file("review/a.md"), Some("review/a.md".to_string()));
+        assert_eq!(file("a.md.tmp"), None);
+        assert_eq!(file("index.json"), None);
+        assert_eq!(file("index.json.tmp"), None);
+        assert_eq!(file(".lock"), None);
+        assert_eq!(file(".tmp/a.md"), None);
+        assert_eq!(file(".history/a/1.md"), None);
+        assert_eq!(library_path(root, Path::new("/elsewhere/a.md"), &any), None);
+
+        let removed = EventKind::Remove(RemoveKind::Any);
+        let folder = |rel: &str, kind: &EventKind| library_path(root, &root.join(rel), kind);
+        assert_eq!(folder("review", &any), None);
+        assert_eq!(folder("review", &removed), Some("review".to_string()));
+        assert_eq!(
+            folder("v1.2", &EventKind::Remove(RemoveKind::Folder)),
+            Some("v1.2".to_string())
+        );
+        assert_eq!(folder("a.md.tmp", &removed), None);
+        assert_eq!(folder(".history/a", &removed), None);
+        assert_eq!(library_path(root, root, &removed), None);
+    }
+
+    #[test]
+    fn reports_a_burst_of_changes_once() {
+        let root = temp_root("burst");
+        fs::create_dir_all(root.join("review")).expect("create folder");
+        let (tx, rx) = mpsc::channel();
+        let watcher = watch(&root, move |files| {
+            let _ = tx.send(files);
+        })
+        .expect("start watcher");
+
+        fs::write(root.join("a.md.tmp"), "draft").expect("write temp file");
+        fs::rename(root.join("a.md.tmp"), root.join("a.md")).expect("rename into place");
+        fs::write(root.join("review").join("b.md"), "hello").expect("write prompt");
+        fs::write(root.join("index.json"), "{}").expect("write index");
+
+        let files = rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("change reported");
+        assert_eq!(files, vec!["a.md".to_string(), "review/b.md".to_string()]);
+        assert!(rx.recv_timeout(DEBOUNCE * 3).is_err());
+
+        drop(watcher);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn reports_folders_renamed_or_removed_as_a_whole() {
+        let root = temp_root("folders");
+        fs::create_dir_all(root.join("review")).expect("create folder");
+        fs::write(root.join("review").join("a.md"), "A").expect("write prompt");
+        fs::create_dir_all(root.join("drafts")).expect("create folder");
+        let (tx, rx) = mpsc::channel();
+        let watcher = watch(&root, move |files| {
+            let _ = tx.send(files);
+        })
+        .expect("start watcher");
+
+        fs::rename(root.join("review"), root.join("reviews")).expect("rename folder");
+        fs::remove_dir(root.join("drafts")).expect("remove folder");
+
+        let mut reported = BTreeSet::new();
+        while let Ok(files) = rx.recv_timeout(Duration::from_secs(2)) {
+            reported.extend(files);
+        }
+        for folder in ["review", "reviews", "drafts"] {
+            assert!(reported.contains(folder), "{folder} in {reported:?}");
+        }
+
+        drop(watcher);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -167,6 +167,21 @@ function App() {
     editInMainWindow: (prompt) => {
       void invoke("open_main_window_for_prompt", { promptId: prompt.id });
     },
+    conflicts: store.conflicts,
+    resolveConflict: (conflict, keep) => {
+      void store.resolvePromptConflict(conflict, keep);
+    },
+    pendingDeletion: store.pendingDeletion,
+    confirmDeletion: () => {
+      void store.confirmDeletion().then((saved) => {
+        if (saved) showSaveToast();
+      });
+    },
+    cancelDeletion: () => {
+      void store.cancelDeletion();
+    },
+    recoveries: store.recoveries,
+    dismissRecoveries: store.dismissRecoveries,
   };
 
   const openMainWindow = async () => {
@@ -0,0 +1,41 @@
+import { TriangleAlert } from "lucide-react";
+
+import { Button } from "@/components/ui/button";
+import { usePromptStoreContext } from "@/contexts/prompt-store-context";
+import { cn } from "@/lib/utils";
+
+type IndexRecoveryBannerProps = {
+  className?: string;
+};
+
+export function IndexRecoveryBanner({ className }: IndexRecoveryBannerProps) {
+  const { recoveries, dismissRecoveries } = usePromptStoreContext();
+  if (recoveries.length === 0) return null;
+
+  return (
+    <div
+      className={cn(
+        "flex items-start gap-2 rounded-md border border-warning/40 bg-warning/8 px-2 py-1.5 text-[11px]",
+        className,
+      )}
+      role="alert"
+    >
+      <TriangleAlert className="mt-0.5 size-3.5 shrink-0 text-warning" />
+      <ul className="min-w-0 flex-1 text-foreground">
+        {recoveries.map((recovery) => (
+          <li
+            key={`${recovery.library}:${recovery.quarantined ?? ""}:${recovery.salvaged}:${recovery.fromBackup}`}
+            title={recovery.library}
+          >
+            The prompt index was damaged and has been rebuilt ({recovery.salvaged} entries kept,{" "}
+            {recovery.fromBackup} from the backup).
+            {recovery.quarantined ? ` The damaged file was kept as ${recovery.quarantined}.` : ""}
+          </li>
+        ))}
+      </ul>
+      <Button size="xs" variant="ghost" onClick={dismissRecoveries}>
+        Dismiss
+      </Button>
+    </div>
+  );
+}
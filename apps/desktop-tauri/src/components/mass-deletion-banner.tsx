@@ -0,0 +1,36 @@
+import { TriangleAlert } from "lucide-react";
+
+import { Button } from "@/components/ui/button";
+import { usePromptStoreContext } from "@/contexts/prompt-store-context";
+import { cn } from "@/lib/utils";
+
+type MassDeletionBannerProps = {
+  className?: string;
+};
+
+export function MassDeletionBanner({ className }: MassDeletionBannerProps) {
+  const { pendingDeletion, confirmDeletion, cancelDeletion } = usePromptStoreContext();
+  if (!pendingDeletion) return null;
+
+  const { existing, removing } = pendingDeletion;
+  return (
+    <div
+      className={cn(
+        "flex items-center gap-2 rounded-md border border-destructive/40 bg-destructive/8 px-2 py-1.5 text-[11px]",
+        className,
+      )}
+      role="alert"
+    >
+      <TriangleAlert className="size-3.5 shrink-0 text-destructive" />
+      <p className="min-w-0 flex-1 text-foreground">
+        Delete {removing} of {existing} prompts? They will be moved to the trash.
+      </p>
+      <Button size="xs" variant="ghost" onClick={cancelDeletion}>
+        Keep
+      </Button>
+      <Button size="xs" variant="destructive" onClick={confirmDeletion}>
+        Delete
+      </Button>
+    </div>
+  );
+}
@@ -194,9 +194,13 @@ export function PromptCardMain({ prompt, dragHandle }: { prompt: Prompt; dragHan
       </div>
       {isExpanded ? (
         <div className="border-t border-border px-2.5 pb-2.5 pt-2">
+          {prompt.loadError ? (
+            <p className="mb-2 text-xs text-destructive">{prompt.loadError}</p>
+          ) : null}
           <Textarea
             className="min-h-[140px] resize-y font-mono text-[13px]"
             value={prompt.content}
+            readOnly={Boolean(prompt.loadError)}
             onChange={(event) => store.changeContent(prompt.id, event.target.value)}
           />
         </div>
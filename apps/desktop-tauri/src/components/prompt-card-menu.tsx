@@ -1,8 +1,15 @@
 import { type RefObject, useLayoutEffect, useRef } from "react";
 import { createPortal } from "react-dom";
-import { Pencil, Trash2 } from "lucide-react";
+import { Pencil, SquarePen, Trash2 } from "lucide-react";
 
 import { IconCopyPath, IconCursor, IconVscode, IconZed } from "@/components/icons";
+import { useEditors } from "@/hooks/use-editors";
+
+const EDITOR_ICONS: Record<string, typeof IconCursor> = {
+  cursor: IconCursor,
+  vscode: IconVscode,
+  zed: IconZed,
+};
 
 type PromptCardMenuProps = {
   open: boolean;
@@ -11,12 +18,13 @@ type PromptCardMenuProps = {
   onEdit?: () => void;
   onDelete?: () => void;
   deleteConfirm?: boolean;
-  onOpenInEditor: (editor: "cursor" | "vscode" | "zed") => void;
+  onOpenInEditor: (editorId: string) => void;
   onCopyPath: () => void;
 };
 
 export function PromptCardMenu({ open, triggerRef, portalRef, onEdit, onDelete, deleteConfirm, onOpenInEditor, onCopyPath }: PromptCardMenuProps) {
   const innerRef = useRef<HTMLDivElement>(null);
+  const editors = useEditors();
 
   useLayoutEffect(() => {
     const el = innerRef.current;
@@ -55,18 +63,15 @@ export function PromptCardMenu({ open, triggerRef, portalRef, onEdit, onDelete,
           <span className="truncate whitespace-nowrap">Edit</span>
         </button>
       ) : null}
-      <button className={itemClass} onClick={() => onOpenInEditor("cursor")}>
-        <IconCursor className="size-3.5" />
-        <span className="truncate whitespace-nowrap">Open in Cursor</span>
-      </button>
-      <button className={itemClass} onClick={() => onOpenInEditor("vscode")}>
-        <IconVscode className="size-3.5" />
-        <span className="truncate whitespace-nowrap">Open in VS Code</span>
-      </button>
-      <button className={itemClass} onClick={() => onOpenInEditor("zed")}>
-        <IconZed className="size-3.5" />
-        <span className="truncate whitespace-nowrap">Open in Zed</span>
-      </button>
+      {editors.map((editor) => {
+        const Icon = EDITOR_ICONS[editor.id];
+        return (
+          <button key={editor.id} className={itemClass} onClick={() => onOpenInEditor(editor.id)}>
+            {Icon ? <Icon className="size-3.5" /> : <SquarePen className="size-3.5" />}
+            <span className="truncate whitespace-nowrap">Open in {editor.name}</span>
+          </button>
+        );
+      })}
       <button className={itemClass} onClick={onCopyPath}>
         <IconCopyPath className="size-3.5" />
         <span className="truncate whitespace-nowrap">Copy Path</span>
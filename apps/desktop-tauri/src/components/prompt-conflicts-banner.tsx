@@ -0,0 +1,38 @@
+import { TriangleAlert } from "lucide-react";
+
+import { Button } from "@/components/ui/button";
+import { usePromptStoreContext } from "@/contexts/prompt-store-context";
+import { cn } from "@/lib/utils";
+
+type PromptConflictsBannerProps = {
+  className?: string;
+};
+
+export function PromptConflictsBanner({ className }: PromptConflictsBannerProps) {
+  const { conflicts, resolveConflict } = usePromptStoreContext();
+  if (conflicts.length === 0) return null;
+
+  return (
+    <div
+      className={cn("rounded-md border border-warning/40 bg-warning/8 px-2 py-1.5 text-[11px]", className)}
+      role="alert"
+    >
+      <ul className="flex flex-col gap-1">
+        {conflicts.map((conflict) => (
+          <li key={conflict.promptId} className="flex items-center gap-2">
+            <TriangleAlert className="size-3.5 shrink-0 text-warning" />
+            <p className="min-w-0 flex-1 truncate text-foreground" title={conflict.file}>
+              {conflict.local.title || conflict.file} changed outside PromptBook
+            </p>
+            <Button size="xs" variant="ghost" onClick={() => resolveConflict(conflict, "disk")}>
+              Use file
+            </Button>
+            <Button size="xs" variant="outline" onClick={() => resolveConflict(conflict, "local")}>
+              Keep mine
+            </Button>
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
@@ -1,6 +1,9 @@
 import type { RefObject } from "react";
 
+import { IndexRecoveryBanner } from "@/components/index-recovery-banner";
 import { KeyboardShortcuts } from "@/components/keyboard-shortcuts";
+import { MassDeletionBanner } from "@/components/mass-deletion-banner";
+import { PromptConflictsBanner } from "@/components/prompt-conflicts-banner";
 import { PromptList } from "@/components/prompt-list";
 import { PromptListEmptyMain } from "@/components/prompt-list-empty";
 import { SaveToast } from "@/components/save-toast";
@@ -26,6 +29,9 @@ export function WindowMainView({ saveToastVisible, contentRef, onStartDrag }: Wi
         <div className="shrink-0 flex items-center gap-2 pb-2">
           <SearchBar value={search} onChange={setSearch} onAdd={addPrompt} className="flex-1" />
         </div>
+        <MassDeletionBanner className="mb-2 shrink-0" />
+        <PromptConflictsBanner className="mb-2 shrink-0" />
+        <IndexRecoveryBanner className="mb-2 shrink-0" />
         {isEmpty ? (
           <PromptListEmptyMain />
         ) : (
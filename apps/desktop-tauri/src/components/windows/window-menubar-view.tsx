@@ -2,7 +2,10 @@ import { invoke } from "@tauri-apps/api/core";
 import { ExternalLink } from "lucide-react";
 import { type RefObject, useEffect } from "react";
 
+import { IndexRecoveryBanner } from "@/components/index-recovery-banner";
+import { MassDeletionBanner } from "@/components/mass-deletion-banner";
 import { hasSelectedText } from "@/components/prompt-card-utils";
+import { PromptConflictsBanner } from "@/components/prompt-conflicts-banner";
 import { PromptList } from "@/components/prompt-list";
 import { PromptListEmptyMenubar } from "@/components/prompt-list-empty";
 import { SaveToast } from "@/components/save-toast";
@@ -70,6 +73,9 @@ export function WindowMenubarView({
         >
           <div ref={headerRef} className="shrink-0 px-3 pb-2 pt-3">
             <SearchBar autoFocus value={search} onChange={setSearch} onAdd={addAndOpen} className="flex-1" />
+            <MassDeletionBanner className="mt-2" />
+            <PromptConflictsBanner className="mt-2" />
+            <IndexRecoveryBanner className="mt-2" />
           </div>
           {isEmpty ? (
             <div className="flex min-h-0 flex-1 px-3 pb-2.5 pt-0">
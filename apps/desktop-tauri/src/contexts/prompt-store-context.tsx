@@ -1,5 +1,6 @@
 import { createContext, useContext } from "react";
-import type { Prompt } from "@/types/prompt";
+import type { IndexRecovery, PendingDeletion } from "@/hooks/use-prompt-persistence";
+import type { Prompt, PromptConflict } from "@/types/prompt";
 
 export type PromptStoreContextType = {
   promptCount: number;
@@ -24,9 +25,16 @@ export type PromptStoreContextType = {
   cancelEditTitle: () => void;
   requestDeleteConfirm: (id: string) => void;
   deletePrompt: (id: string) => void;
-  openInEditor: (prompt: Prompt, editor: "cursor" | "vscode" | "zed") => void;
+  openInEditor: (prompt: Prompt, editorId: string) => void;
   copyPath: (prompt: Prompt) => void;
   editInMainWindow: (prompt: Prompt) => void;
+  conflicts: PromptConflict[];
+  resolveConflict: (conflict: PromptConflict, keep: "local" | "disk") => void;
+  pendingDeletion: PendingDeletion | null;
+  confirmDeletion: () => void;
+  cancelDeletion: () => void;
+  recoveries: IndexRecovery[];
+  dismissRecoveries: () => void;
 };
 
 const PromptStoreContext = createContext<PromptStoreContextType | null>(null);
@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useCallback, useEffect, useState } from "react";
+
+import { useTauriEvent } from "@/hooks/use-tauri-event";
+import type { EditorCommand } from "@/types/editor";
+
+export function useEditors() {
+  const [editors, setEditors] = useState<EditorCommand[]>([]);
+
+  const reload = useCallback(async () => {
+    try {
+      setEditors(await invoke<EditorCommand[]>("list_editors"));
+    } catch (error) {
+      console.error("Failed to load editors:", error);
+    }
+  }, []);
+
+  useEffect(() => {
+    void reload();
+  }, [reload]);
+
+  useTauriEvent("editors-updated", () => {
+    void reload();
+  });
+
+  return editors;
+}
@@ -4,11 +4,42 @@ import { useCallback, useEffect, useRef, useState } from "react";
 
 import { useTauriEvent } from "@/hooks/use-tauri-event";
 import { UNNAMED_PROMPT_TITLE } from "@/lib/constants";
-import type { Prompt } from "@/types/prompt";
+import { describeStorageError, isStorageError } from "@/lib/storage-error";
+import type { Prompt, PromptConflict } from "@/types/prompt";
 
 const AUTOSAVE_DEBOUNCE_MS = 220;
 
-type PromptsUpdatedPayload = { source: string };
+type PromptsUpdatedPayload = {
+  source: string;
+  changed?: string[];
+  deleted?: string[];
+  order?: string[];
+  /** Files changed outside the app, sent by the storage watcher. */
+  files?: string[];
+};
+
+/** A save refused by the mass-deletion guard, waiting for the user to confirm. */
+export type PendingDeletion = { existing: number; removing: number };
+
+/** A broken index that storage rebuilt; see `storage/recovery.rs`. */
+export type IndexRecovery = {
+  library: string;
+  quarantined: string | null;
+  salvaged: number;
+  fromBackup: number;
+};
+
+function promptEqual(left: Prompt, right: Prompt): boolean {
+  return (
+    left.id === right.id &&
+    left.title === right.title &&
+    left.content === right.content &&
+    left.copied === right.copied &&
+    left.searched === right.searched &&
+    (left.folder ?? "") === (right.folder ?? "") &&
+    (left.tags ?? []).join("\n") === (right.tags ?? []).join("\n")
+  );
+}
 
 function promptsEqual(a: Prompt[], b: Prompt[]): boolean {
   if (a.length !== b.length) return false;
@@ -16,26 +47,65 @@ function promptsEqual(a: Prompt[], b: Prompt[]): boolean {
     const left = a[i];
     const right = b[i];
     if (!left || !right) return false;
-    if (
-      left.id !== right.id ||
-      left.title !== right.title ||
-      left.content !== right.content ||
-      left.copied !== right.copied ||
-      left.searched !== right.searched
-    ) {
-      return false;
-    }
+    if (!promptEqual(left, right)) return false;
   }
   return true;
 }
 
+// Sends only what changed since the last persisted snapshot so storage can
+// touch the affected files instead of rewriting the whole library. Removals go
+// in one call so storage can refuse a mass deletion unless `force` is set.
+// Prompts in `held` wait on a conflict and are not sent; the ids of prompts
+// whose file turns out to have changed on disk are returned instead of thrown,
+// so one conflict does not hold up the rest of the batch.
+async function persistChanges(
+  previous: Prompt[],
+  next: Prompt[],
+  force: boolean,
+  held: ReadonlySet<string>,
+): Promise<string[]> {
+  const previousById = new Map(previous.map((p) => [p.id, p]));
+  const nextIds = new Set(next.map((p) => p.id));
+
+  const removed = previous.filter((p) => !nextIds.has(p.id)).map((p) => p.id);
+  if (removed.length > 0) await invoke("delete_prompts", { promptIds: removed, force });
+
+  const conflicted: string[] = [];
+  const expectedOrder = previous.filter((p) => nextIds.has(p.id)).map((p) => p.id);
+  for (const prompt of next) {
+    const before = previousById.get(prompt.id);
+    if (!before) {
+      await invoke("create_prompt", { prompt });
+      expectedOrder.push(prompt.id);
+    } else if (!held.has(prompt.id) && !promptEqual(before, prompt)) {
+      try {
+        await invoke("update_prompt", { prompt });
+      } catch (error) {
+        if (!isStorageError(error) || error.kind !== "changedOnDisk") throw error;
+        conflicted.push(...error.promptIds);
+      }
+    }
+  }
+
+  const order = next.map((p) => p.id);
+  if (order.join("\n") !== expectedOrder.join("\n")) {
+    await invoke("reorder_prompts", { ids: order });
+  }
+  return conflicted;
+}
+
 export function usePromptPersistence(callbacks?: {
   onInitialLoad?: (prompts: Prompt[]) => void;
   onExternalReload?: (prompts: Prompt[]) => void;
 }) {
   const [prompts, setPrompts] = useState<Prompt[]>([]);
+  const [conflicts, setConflicts] = useState<PromptConflict[]>([]);
+  const [pendingDeletion, setPendingDeletion] = useState<PendingDeletion | null>(null);
+  const [recoveries, setRecoveries] = useState<IndexRecovery[]>([]);
   const promptsRef = useRef<Prompt[]>([]);
+  const conflictsRef = useRef<PromptConflict[]>([]);
   const loadedRef = useRef(false);
+  const persistedRef = useRef<Prompt[] | null>(null);
   const skipNextPersistRef = useRef(false);
   const windowLabelRef = useRef(getCurrentWindow().label);
   const onInitialLoadRef = useRef(callbacks?.onInitialLoad);
@@ -51,48 +121,169 @@ export function usePromptPersistence(callbacks?: {
     });
   }, []);
 
-  const save = useCallback(async (data: Prompt[]): Promise<boolean> => {
+  const updateConflicts = useCallback((updater: (prev: PromptConflict[]) => PromptConflict[]) => {
+    setConflicts((prev) => {
+      const next = updater(prev);
+      conflictsRef.current = next;
+      return next;
+    });
+  }, []);
+
+  const loadConflicts = useCallback(
+    async (promptIds: string[]) => {
+      const loaded = await Promise.all(
+        promptIds.map((promptId) => invoke<PromptConflict>("get_prompt_conflict", { promptId })),
+      );
+      updateConflicts((prev) => prev.filter((c) => !promptIds.includes(c.promptId)).concat(loaded));
+    },
+    [updateConflicts],
+  );
+
+  // Prompts waiting on a conflict keep the in-app edit until the user picks a
+  // version, whatever a reload brings in.
+  const keepConflictedEdits = useCallback((loaded: Prompt[]): Prompt[] => {
+    if (conflictsRef.current.length === 0) return loaded;
+    const local = new Map(
+      conflictsRef.current.map((c) => [c.promptId, promptsRef.current.find((p) => p.id === c.promptId) ?? c.local]),
+    );
+    return loaded.map((p) => local.get(p.id) ?? p);
+  }, []);
+
+  const save = useCallback(async (data: Prompt[], force = false): Promise<boolean> => {
     try {
       const hasEmpty = data.some((p) => !p.title.trim());
       const normalized = hasEmpty
         ? data.map((p) => (p.title.trim() ? p : { ...p, title: UNNAMED_PROMPT_TITLE }))
         : data;
       if (hasEmpty) updatePrompts(normalized);
-      await invoke("save_prompts", { prompts: normalized });
-      return true;
+      const previous = persistedRef.current;
+      const held = new Set(conflictsRef.current.map((c) => c.promptId));
+      let conflicted: string[] = [];
+      if (previous) {
+        try {
+          conflicted = await persistChanges(previous, normalized, force, held);
+        } catch (error) {
+          if (isStorageError(error) && (error.kind === "changedOnDisk" || error.kind === "massDeletion")) throw error;
+          console.error("Failed to save prompt changes, saving everything:", error);
+          await invoke("save_prompts", { prompts: normalized, force });
+        }
+      } else {
+        await invoke("save_prompts", { prompts: normalized, force });
+      }
+      setPendingDeletion(null);
+      if (conflicted.length === 0 && held.size === 0) {
+        persistedRef.current = normalized;
+        return true;
+      }
+      // What was not written stays as it was, so it is sent again once resolved.
+      const unsaved = new Set([...held, ...conflicted]);
+      const previousById = new Map((previous ?? []).map((p) => [p.id, p]));
+      persistedRef.current = normalized.map((p) => (unsaved.has(p.id) ? previousById.get(p.id) ?? p : p));
+      // Awaited, so a reload that follows the save knows which edits to keep.
+      if (conflicted.length > 0) {
+        await loadConflicts(conflicted).catch((e) => console.error("Failed to load conflicts:", e));
+      }
+      return conflicted.length === 0;
     } catch (error) {
-      console.error("Failed to save prompts:", error);
+      console.error("Failed to save prompts:", describeStorageError(error));
+      if (isStorageError(error) && error.kind === "changedOnDisk") {
+        void loadConflicts(error.promptIds).catch((e) => console.error("Failed to load conflicts:", e));
+      }
+      if (isStorageError(error) && error.kind === "massDeletion") {
+        setPendingDeletion({ existing: error.existing, removing: error.removing });
+      }
       return false;
     }
-  }, []);
+  }, [loadConflicts]);
 
-  const reloadPrompts = useCallback(async () => {
-    try {
-      const loaded = await invoke<Prompt[]>("load_prompts");
+  const applyExternalPrompts = useCallback(
+    (compute: (prev: Prompt[]) => Prompt[]) => {
       let didChange = false;
+      let applied: Prompt[] = [];
       updatePrompts((prev) => {
-        if (promptsEqual(prev, loaded)) return prev;
+        const next = compute(prev);
+        if (promptsEqual(prev, next)) return prev;
         didChange = true;
-        return loaded;
+        applied = next;
+        return next;
       });
       if (!didChange) return;
+      persistedRef.current = applied;
       skipNextPersistRef.current = true;
-      onExternalReloadRef.current?.(loaded);
+      onExternalReloadRef.current?.(applied);
+    },
+    [updatePrompts],
+  );
+
+  const reloadPrompts = useCallback(async () => {
+    try {
+      const loaded = await invoke<Prompt[]>("load_prompts");
+      applyExternalPrompts(() => keepConflictedEdits(loaded));
+      persistedRef.current = loaded;
     } catch (error) {
       console.error("Failed to load prompts:", error);
     }
-  }, [updatePrompts, save]);
+  }, [applyExternalPrompts, keepConflictedEdits]);
+
+  const patchPrompts = useCallback(
+    async (payload: PromptsUpdatedPayload) => {
+      try {
+        const changed = payload.changed ?? [];
+        const fetched =
+          changed.length > 0 ? keepConflictedEdits(await invoke<Prompt[]>("get_prompts", { ids: changed })) : [];
+        const fetchedById = new Map(fetched.map((p) => [p.id, p]));
+        const deleted = new Set(payload.deleted ?? []);
+        const position = payload.order ? new Map(payload.order.map((id, i) => [id, i])) : null;
+        applyExternalPrompts((prev) => {
+          let next = prev.filter((p) => !deleted.has(p.id)).map((p) => fetchedById.get(p.id) ?? p);
+          const known = new Set(next.map((p) => p.id));
+          next = next.concat(fetched.filter((p) => !known.has(p.id)));
+          if (position) {
+            const rank = (p: Prompt) => position.get(p.id) ?? Number.MAX_SAFE_INTEGER;
+            next = [...next].sort((a, b) => rank(a) - rank(b));
+          }
+          return next;
+        });
+      } catch (error) {
+        console.error("Failed to patch prompts:", error);
+        await reloadPrompts();
+      }
+    },
+    [applyExternalPrompts, keepConflictedEdits, reloadPrompts],
+  );
+
+  // Saves the version the user picked (or merged) and drops the conflict.
+  const resolveConflict = useCallback(
+    async (prompt: Prompt) => {
+      await invoke("resolve_prompt_conflict", { prompt });
+      updateConflicts((prev) => prev.filter((c) => c.promptId !== prompt.id));
+      await reloadPrompts();
+    },
+    [reloadPrompts, updateConflicts],
+  );
 
   const forceSave = useCallback(async (): Promise<boolean> => {
     return save(promptsRef.current);
   }, [save]);
 
+  // The user meant it: delete past the mass-deletion guard.
+  const confirmDeletion = useCallback(async (): Promise<boolean> => {
+    return save(promptsRef.current, true);
+  }, [save]);
+
+  // Brings back the prompts the refused save would have deleted.
+  const cancelDeletion = useCallback(async () => {
+    setPendingDeletion(null);
+    await reloadPrompts();
+  }, [reloadPrompts]);
+
   useEffect(() => {
     let cancelled = false;
     void (async () => {
       try {
         const loaded = await invoke<Prompt[]>("load_prompts");
         if (cancelled) return;
+        persistedRef.current = loaded;
         if (loaded.length > 0) {
           updatePrompts(loaded);
           onInitialLoadRef.current?.(loaded);
@@ -121,10 +312,40 @@ export function usePromptPersistence(callbacks?: {
   }, [prompts, save]);
 
   useTauriEvent<PromptsUpdatedPayload>("prompts-updated", (event) => {
-    const source = event.payload?.source;
-    if (source && source === windowLabelRef.current) return;
+    const payload = event.payload;
+    if (payload?.source && payload.source === windowLabelRef.current) return;
+    if (payload?.files) {
+      // Flush pending edits first so the reload cannot drop them; prompts that
+      // conflict with the change keep their edit until resolved.
+      void forceSave().then(reloadPrompts);
+      return;
+    }
+    if (payload?.changed || payload?.deleted || payload?.order) {
+      void patchPrompts(payload);
+      return;
+    }
     void reloadPrompts();
   });
 
-  return { prompts, promptsRef, updatePrompts, save, reloadPrompts, forceSave };
+  useTauriEvent<IndexRecovery>("index-recovered", (event) => {
+    setRecoveries((prev) => [...prev, event.payload]);
+  });
+
+  const dismissRecoveries = useCallback(() => setRecoveries([]), []);
+
+  return {
+    prompts,
+    promptsRef,
+    updatePrompts,
+    save,
+    reloadPrompts,
+    forceSave,
+    conflicts,
+    resolveConflict,
+    pendingDeletion,
+    confirmDeletion,
+    cancelDeletion,
+    recoveries,
+    dismissRecoveries,
+  };
 }
@@ -3,7 +3,7 @@ import { useCallback, useMemo, useState } from "react";
 
 import { usePromptPersistence } from "@/hooks/use-prompt-persistence";
 import { UNNAMED_PROMPT_TITLE } from "@/lib/constants";
-import type { Prompt } from "@/types/prompt";
+import type { Prompt, PromptConflict } from "@/types/prompt";
 
 const DELETE_CONFIRM_TIMEOUT_MS = 1600;
 const COPY_FEEDBACK_TIMEOUT_MS = 1000;
@@ -30,7 +30,21 @@ export function usePromptStore() {
     setExpandedId((prev) => (prev && loaded.some((p) => p.id === prev) ? prev : loaded[0]?.id ?? ""));
   }, []);
 
-  const { prompts, promptsRef, updatePrompts, save, reloadPrompts, forceSave } = usePromptPersistence({
+  const {
+    prompts,
+    promptsRef,
+    updatePrompts,
+    save,
+    reloadPrompts,
+    forceSave,
+    conflicts,
+    resolveConflict,
+    pendingDeletion,
+    confirmDeletion,
+    cancelDeletion,
+    recoveries,
+    dismissRecoveries,
+  } = usePromptPersistence({
     onInitialLoad: handleInitialLoad,
     onExternalReload: handleExternalReload,
   });
@@ -132,9 +146,10 @@ export function usePromptStore() {
     updatePrompts(reordered);
   };
 
-  const openPromptInEditor = async (prompt: Prompt, editor: "cursor" | "vscode" | "zed") => {
+  const openPromptInEditor = async (prompt: Prompt, editor: string) => {
     try {
-      await invoke("open_prompt_in_editor", { editor, title: prompt.title, content: prompt.content });
+      await save(promptsRef.current);
+      await invoke("open_prompt_in_editor", { editor, promptId: prompt.id, title: prompt.title });
     } catch (error) {
       console.error(`Failed to open prompt in ${editor}:`, error);
     }
@@ -150,6 +165,17 @@ export function usePromptStore() {
     }
   };
 
+  // Keeping our side saves what is in the app now, which may be newer than
+  // the version the refused save tried to write.
+  const resolvePromptConflict = async (conflict: PromptConflict, keep: "local" | "disk") => {
+    const local = promptsRef.current.find((p) => p.id === conflict.promptId) ?? conflict.local;
+    try {
+      await resolveConflict(keep === "local" ? local : conflict.disk);
+    } catch (error) {
+      console.error("Failed to resolve prompt conflict:", error);
+    }
+  };
+
   return {
     prompts,
     filteredPrompts,
@@ -178,5 +204,12 @@ export function usePromptStore() {
     copyPromptPath,
     reloadPrompts,
     forceSave,
+    conflicts,
+    resolvePromptConflict,
+    pendingDeletion,
+    confirmDeletion,
+    cancelDeletion,
+    recoveries,
+    dismissRecoveries,
   };
 }
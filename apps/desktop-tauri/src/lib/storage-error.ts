@@ -0,0 +1,23 @@
+import type { StorageError } from "@/types/storage-error";
+
+export function isStorageError(error: unknown): error is StorageError {
+  return typeof error === "object" && error !== null && typeof (error as { kind?: unknown }).kind === "string";
+}
+
+export function describeStorageError(error: unknown): string {
+  if (!isStorageError(error)) return String(error);
+  switch (error.kind) {
+    case "homeMissing":
+      return "HOME is not set. Set PROMPTBOOK_HOME to choose where prompts are stored.";
+    case "changedOnDisk":
+      return `${error.promptIds.length === 1 ? "A prompt was" : "Some prompts were"} changed outside PromptBook. Choose which version to keep.`;
+    case "massDeletion":
+      return `Refusing to delete ${error.removing} of ${error.existing} prompts in one save.`;
+    case "permissionDenied":
+      return `${error.message}. Check the folder permissions or choose another storage location.`;
+    case "diskFull":
+      return `${error.message}. Free up some disk space and try again.`;
+    default:
+      return error.message;
+  }
+}
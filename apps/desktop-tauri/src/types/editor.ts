@@ -0,0 +1,6 @@
+/** Mirrors `storage::EditorCommand`. `command` is a template such as `code {path}`. */
+export type EditorCommand = {
+  id: string;
+  name: string;
+  command: string;
+};
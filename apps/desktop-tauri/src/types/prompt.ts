@@ -4,4 +4,16 @@ export type Prompt = {
   content: string;
   copied: number;
   searched: number;
+  tags?: string[];
+  folder?: string;
+  /** Set when the file is not text PromptBook can read; the prompt is read-only. */
+  loadError?: string;
+};
+
+/** A save refused because the file changed on disk; see `get_prompt_conflict`. */
+export type PromptConflict = {
+  promptId: string;
+  file: string;
+  local: Prompt;
+  disk: Prompt;
 };
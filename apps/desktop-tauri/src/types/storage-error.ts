@@ -0,0 +1,15 @@
+/** Mirrors `storage::StorageError`; every command that touches storage rejects with one. */
+export type StorageError =
+  | { kind: "notFound"; message: string }
+  | { kind: "permissionDenied"; message: string }
+  | { kind: "diskFull"; message: string }
+  | { kind: "conflict"; message: string }
+  | { kind: "corruptIndex"; message: string }
+  | { kind: "homeMissing" }
+  | { kind: "invalid"; message: string }
+  | { kind: "unreadable"; message: string }
+  | { kind: "locked"; message: string }
+  | { kind: "changedOnDisk"; promptIds: string[] }
+  | { kind: "editor"; message: string }
+  | { kind: "massDeletion"; existing: number; removing: number }
+  | { kind: "io"; message: string };